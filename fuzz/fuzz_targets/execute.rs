@@ -0,0 +1,53 @@
+//! Runs arbitrary text as a script, a line at a time as the program does, checking that nothing
+//! panics and the board stays valid throughout. The first byte picks the grid.
+//!
+//! Run with `cargo +nightly fuzz run execute`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_toy_robot::game_execution::is_board_valid;
+use rust_toy_robot::game_model::{Board, EnergyModel};
+use rust_toy_robot::geo::grid::{GridShape, HexGrid, SquareGrid};
+use rust_toy_robot::geo::{Compass, Vector};
+use rust_toy_robot::session::run_line;
+
+const MAX_LINES: usize = 64;
+
+fuzz_target!(|data: &[u8]| {
+    let (grid, script) = match data.split_first() {
+        Some((0, rest)) => (GridShape::Hex(HexGrid), rest),
+        Some((1, rest)) => (
+            GridShape::Square(SquareGrid {
+                compass: Compass::EightWay,
+            }),
+            rest,
+        ),
+        Some((_, rest)) => (
+            GridShape::Square(SquareGrid {
+                compass: Compass::FourWay,
+            }),
+            rest,
+        ),
+        None => return,
+    };
+
+    let script = match std::str::from_utf8(script) {
+        Ok(script) => script,
+        Err(_) => return,
+    };
+
+    let mut board = Board::empty_with_corner(&Vector::at_level(4, 4, 1))
+        .with_grid(grid)
+        .with_energy_model(EnergyModel {
+            capacity: 20,
+            move_cost: 1,
+            rotate_cost: 1,
+            carpet_cost: 2,
+        });
+
+    for line in script.lines().take(MAX_LINES) {
+        board = run_line(&board, line).0;
+
+        assert!(is_board_valid(&board), "invalid board after {:?}", line);
+    }
+});
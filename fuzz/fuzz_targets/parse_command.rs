@@ -0,0 +1,12 @@
+//! Parses arbitrary text as a command, which should fail rather than panic on anything it does
+//! not understand.
+//!
+//! Run with `cargo +nightly fuzz run parse_command`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_toy_robot::commands::parsing::parse_command;
+
+fuzz_target!(|input: &str| {
+    let _ = parse_command(input);
+});
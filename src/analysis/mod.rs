@@ -0,0 +1,457 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::commands::Command;
+use crate::game_execution::{annotated_map_for, step, successors};
+use crate::game_model::{Board, Robot, Terrain};
+use crate::geo::grid::Grid;
+use crate::geo::Direction::*;
+use crate::geo::RelativeDirection::{Left, Right};
+use crate::geo::{Direction, Square, Vector};
+
+/// Every location the robot could reach from where it stands by moving and turning, including
+/// the location it starts on. Energy is ignored, so this is what the layout allows rather than
+/// what the robot's battery allows. An empty board gives an empty set.
+pub fn reachable_locations(board: &Board) -> HashSet<Vector> {
+    reachable_robots(board)
+        .into_iter()
+        .map(|robot| robot.location)
+        .collect()
+}
+
+fn reachable_robots(board: &Board) -> Vec<Robot> {
+    let start = match &board.robot {
+        Some(robot) => robot.with_energy(None),
+        None => return Vec::new(),
+    };
+    let board = board.without_history();
+
+    let mut visited: HashSet<(Vector, Direction)> = HashSet::new();
+    let mut reached: Vec<Robot> = Vec::new();
+    let mut queue: VecDeque<Robot> = VecDeque::new();
+
+    visited.insert((start.location, start.facing));
+    queue.push_back(start);
+
+    while let Some(robot) = queue.pop_front() {
+        for (_, next_board) in successors(
+            &board.with_robot(robot.clone()),
+            &[Command::Move, Command::Rotate(Left), Command::Rotate(Right)],
+        ) {
+            if let Some(next_robot) = next_board.robot {
+                if visited.insert((next_robot.location, next_robot.facing)) {
+                    queue.push_back(next_robot);
+                }
+            }
+        }
+
+        reached.push(robot);
+    }
+
+    reached
+}
+
+/// Every cell on the board a robot could stand on: those without an obstacle or a pit.
+pub fn free_cells(board: &Board) -> Vec<Vector> {
+    let bounds = board.bounds;
+
+    (bounds.bottom_left.z..=bounds.top_right.z)
+        .flat_map(|z| {
+            let level = Square::with_corners(
+                &bounds.bottom_left.with_level(z),
+                &bounds.top_right.with_level(z),
+            );
+
+            board.grid.layout(&level).into_iter().flatten().flatten()
+        })
+        .filter(|location| {
+            !board.obstacle_locations.contains(location)
+                && board.terrain_at(location) != Terrain::Pit
+        })
+        .collect()
+}
+
+/// Which free cells are next to each other, in any direction the board's grid supports and
+/// through ramps, ignoring the robot and which way it faces.
+fn free_space(board: &Board) -> HashMap<Vector, HashSet<Vector>> {
+    let cells: HashSet<Vector> = free_cells(board).into_iter().collect();
+    let mut neighbours: HashMap<Vector, HashSet<Vector>> = cells
+        .iter()
+        .map(|location| (*location, HashSet::new()))
+        .collect();
+
+    for location in &cells {
+        for direction in [
+            North, South, East, West, NorthEast, NorthWest, SouthEast, SouthWest,
+        ] {
+            let is_corner_blocked = board
+                .grid
+                .corners(location, direction)
+                .iter()
+                .any(|corner| board.obstacle_locations.contains(corner));
+
+            if !board.grid.supports(&direction) || is_corner_blocked {
+                continue;
+            }
+
+            let next_location = match step(board, location, direction) {
+                Some(next_location) => next_location,
+                None => continue,
+            };
+
+            if cells.contains(&next_location) && next_location != *location {
+                neighbours
+                    .entry(*location)
+                    .or_default()
+                    .insert(next_location);
+                neighbours
+                    .entry(next_location)
+                    .or_default()
+                    .insert(*location);
+            }
+        }
+    }
+
+    neighbours
+}
+
+/// The separate areas of free space, largest first.
+pub fn connected_components(board: &Board) -> Vec<HashSet<Vector>> {
+    let mut components = components_of(&free_space(board));
+
+    components.sort_by_key(|component| std::cmp::Reverse(component.len()));
+    components
+}
+
+fn components_of(neighbours: &HashMap<Vector, HashSet<Vector>>) -> Vec<HashSet<Vector>> {
+    let mut seen: HashSet<Vector> = HashSet::new();
+    let mut components = Vec::new();
+
+    let mut cells: Vec<&Vector> = neighbours.keys().collect();
+    cells.sort_by_key(|location| (location.z, location.x, location.y));
+
+    for start in cells {
+        if !seen.insert(*start) {
+            continue;
+        }
+
+        let mut component: HashSet<Vector> = HashSet::new();
+        let mut queue: VecDeque<Vector> = VecDeque::new();
+
+        component.insert(*start);
+        queue.push_back(*start);
+
+        while let Some(location) = queue.pop_front() {
+            for next_location in &neighbours[&location] {
+                if seen.insert(*next_location) {
+                    component.insert(*next_location);
+                    queue.push_back(*next_location);
+                }
+            }
+        }
+
+        components.push(component);
+    }
+
+    components
+}
+
+/// Free cells with only one free neighbour.
+pub fn dead_ends(board: &Board) -> HashSet<Vector> {
+    free_space(board)
+        .into_iter()
+        .filter(|(_, neighbours)| neighbours.len() == 1)
+        .map(|(location, _)| location)
+        .collect()
+}
+
+/// Free cells that would split an area of free space in two if they were blocked.
+///
+/// These are the articulation points of the free space, found in a single depth-first pass with
+/// Tarjan's low links: a cell is one if some cell explored from it cannot reach back above it
+/// except through it, or, where the pass started, if more than one cell was explored from it.
+/// The pass keeps its own stack, so a long corridor cannot overflow the call stack.
+pub fn chokepoints(board: &Board) -> HashSet<Vector> {
+    let neighbours = free_space(board);
+    let mut order: HashMap<Vector, usize> = HashMap::new();
+    let mut low: HashMap<Vector, usize> = HashMap::new();
+    let mut chokepoints = HashSet::new();
+
+    for root in neighbours.keys() {
+        if order.contains_key(root) {
+            continue;
+        }
+
+        order.insert(*root, order.len());
+        low.insert(*root, order[root]);
+
+        let mut root_children = 0;
+        // Each cell being explored, the cell it was reached from, and the neighbours left to try.
+        let mut stack: Vec<(Vector, Option<Vector>, Vec<Vector>)> =
+            vec![(*root, None, neighbours[root].iter().copied().collect())];
+
+        while let Some((cell, parent, untried)) = stack.last_mut() {
+            let (cell, parent) = (*cell, *parent);
+
+            match untried.pop() {
+                Some(next) if Some(next) == parent => {}
+                Some(next) => match order.get(&next) {
+                    Some(next_order) => {
+                        let cell_low = low[&cell].min(*next_order);
+                        low.insert(cell, cell_low);
+                    }
+                    None => {
+                        order.insert(next, order.len());
+                        low.insert(next, order[&next]);
+
+                        if cell == *root {
+                            root_children += 1;
+                        }
+
+                        stack.push((
+                            next,
+                            Some(cell),
+                            neighbours[&next].iter().copied().collect(),
+                        ));
+                    }
+                },
+                None => {
+                    stack.pop();
+
+                    if let Some(parent) = parent {
+                        let parent_low = low[&parent].min(low[&cell]);
+                        low.insert(parent, parent_low);
+
+                        if parent != *root && low[&cell] >= order[&parent] {
+                            chokepoints.insert(parent);
+                        }
+                    }
+                }
+            }
+        }
+
+        if root_children > 1 {
+            chokepoints.insert(*root);
+        }
+    }
+
+    chokepoints
+}
+
+/// The output of ANALYZE: a summary of the board's free space, then a MAP of the robot's level
+/// with unreachable cells drawn as `?`, chokepoints as `!` and dead ends as `*`.
+pub fn analysis_report(board: &Board) -> String {
+    let free_cells = free_cells(board);
+    let reachable = reachable_locations(board);
+    let dead_ends = dead_ends(board);
+    let chokepoints = chokepoints(board);
+
+    let sorted = |locations: &HashSet<Vector>| {
+        let mut locations: Vec<&Vector> = locations.iter().collect();
+        locations.sort_by_key(|location| (location.z, location.x, location.y));
+
+        locations
+            .iter()
+            .map(|location| location.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+
+    let mut lines = Vec::new();
+
+    if board.robot.is_some() {
+        lines.push(format!(
+            "REACHABLE {} OF {} FREE CELLS",
+            free_cells
+                .iter()
+                .filter(|location| reachable.contains(location))
+                .count(),
+            free_cells.len()
+        ));
+    }
+
+    lines.push(format!("COMPONENTS {}", connected_components(board).len()));
+    lines.push(
+        format!("DEAD ENDS {}", sorted(&dead_ends))
+            .trim_end()
+            .to_string(),
+    );
+    lines.push(
+        format!("CHOKEPOINTS {}", sorted(&chokepoints))
+            .trim_end()
+            .to_string(),
+    );
+
+    let level = board
+        .robot
+        .as_ref()
+        .map_or(board.bounds.bottom_left.z, |robot| robot.location.z);
+
+    lines.push(annotated_map_for(board, level, |location| {
+        if board.robot.is_some() && !reachable.contains(location) {
+            Some('?')
+        } else if chokepoints.contains(location) {
+            Some('!')
+        } else if dead_ends.contains(location) {
+            Some('*')
+        } else {
+            None
+        }
+    }));
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+
+    mod reachable {
+        use std::collections::HashSet;
+
+        use crate::analysis::reachable_locations;
+        use crate::game_model::{Board, Robot};
+        use crate::geo::Direction::*;
+        use crate::geo::Vector;
+
+        #[test]
+        fn reachable_without_robot() {
+            let board = Board::empty_with_corner(&Vector::new(2, 2));
+
+            assert_eq!(HashSet::new(), reachable_locations(&board))
+        }
+
+        #[test]
+        fn reachable_on_empty_board() {
+            let board = Board::empty_with_corner(&Vector::new(1, 1))
+                .with_robot(Robot::new(Vector::new(0, 0), North));
+
+            let expected: HashSet<Vector> = vec![
+                Vector::new(0, 0),
+                Vector::new(0, 1),
+                Vector::new(1, 0),
+                Vector::new(1, 1),
+            ]
+            .into_iter()
+            .collect();
+
+            assert_eq!(expected, reachable_locations(&board))
+        }
+
+        #[test]
+        fn reachable_behind_wall() {
+            let board = Board::empty_with_corner(&Vector::new(2, 1))
+                .with_obstacle_at(Vector::new(1, 0))
+                .with_obstacle_at(Vector::new(1, 1))
+                .with_robot(Robot::new(Vector::new(0, 0), East));
+
+            let expected: HashSet<Vector> = vec![Vector::new(0, 0), Vector::new(0, 1)]
+                .into_iter()
+                .collect();
+
+            assert_eq!(expected, reachable_locations(&board))
+        }
+    }
+
+    mod free_space {
+        use std::collections::HashSet;
+
+        use crate::analysis::{analysis_report, chokepoints, connected_components, dead_ends};
+        use crate::game_model::{Board, Robot};
+        use crate::geo::Direction::*;
+        use crate::geo::Vector;
+
+        fn set(locations: &[Vector]) -> HashSet<Vector> {
+            locations.iter().copied().collect()
+        }
+
+        /// Two open 2x3 rooms joined by a doorway at (2, 1).
+        fn two_rooms() -> Board {
+            Board::empty_with_corner(&Vector::new(4, 2))
+                .with_obstacle_at(Vector::new(2, 0))
+                .with_obstacle_at(Vector::new(2, 2))
+        }
+
+        #[test]
+        fn components_split_by_wall() {
+            let board = Board::empty_with_corner(&Vector::new(2, 1))
+                .with_obstacle_at(Vector::new(1, 0))
+                .with_obstacle_at(Vector::new(1, 1));
+
+            assert_eq!(
+                vec![
+                    set(&[Vector::new(0, 0), Vector::new(0, 1)]),
+                    set(&[Vector::new(2, 0), Vector::new(2, 1)]),
+                ],
+                connected_components(&board)
+            )
+        }
+
+        #[test]
+        fn components_largest_first() {
+            let board =
+                Board::empty_with_corner(&Vector::new(3, 0)).with_obstacle_at(Vector::new(1, 0));
+
+            assert_eq!(
+                vec![
+                    set(&[Vector::new(2, 0), Vector::new(3, 0)]),
+                    set(&[Vector::new(0, 0)]),
+                ],
+                connected_components(&board)
+            )
+        }
+
+        #[test]
+        fn dead_ends_of_corridor() {
+            let board = Board::empty_with_corner(&Vector::new(3, 0));
+
+            assert_eq!(
+                set(&[Vector::new(0, 0), Vector::new(3, 0)]),
+                dead_ends(&board)
+            )
+        }
+
+        #[test]
+        fn chokepoints_at_doorway() {
+            assert_eq!(
+                set(&[Vector::new(1, 1), Vector::new(2, 1), Vector::new(3, 1)]),
+                chokepoints(&two_rooms())
+            )
+        }
+
+        #[test]
+        fn chokepoints_along_long_corridor() {
+            let board = Board::empty_with_corner(&Vector::new(9_999, 0));
+
+            let chokepoints = chokepoints(&board);
+
+            assert_eq!(9_998, chokepoints.len());
+            assert!(!chokepoints.contains(&Vector::new(0, 0)));
+            assert!(!chokepoints.contains(&Vector::new(9_999, 0)));
+        }
+
+        #[test]
+        fn no_chokepoints_in_open_room() {
+            let board = Board::empty_with_corner(&Vector::new(2, 2));
+
+            assert_eq!(HashSet::new(), chokepoints(&board))
+        }
+
+        #[test]
+        fn report_summary_and_annotated_map() {
+            let board = two_rooms()
+                .with_obstacle_at(Vector::new(4, 1))
+                .with_obstacle_at(Vector::new(3, 0))
+                .with_robot(Robot::new(Vector::new(0, 0), North));
+
+            assert_eq!(
+                "REACHABLE 10 OF 11 FREE CELLS\n\
+                 COMPONENTS 2\n\
+                 DEAD ENDS 4,2\n\
+                 CHOKEPOINTS 1,1 2,1 3,1 3,2\n\
+                 00X!*\n\
+                 0!!!X\n\
+                 ^0XX?",
+                analysis_report(&board)
+            )
+        }
+    }
+}
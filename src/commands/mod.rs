@@ -1,13 +1,60 @@
+use crate::game_model::{Item, Ramp, Terrain};
+use crate::generation::Layout;
 use crate::geo::{Direction, RelativeDirection, Vector};
 
 pub mod parsing;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub enum Command {
-    Place { location: Vector, facing: Direction },
+    Place {
+        location: Vector,
+        facing: Direction,
+    },
     PlaceObject,
+    PlaceObjectAt(Vector),
+    RemoveObject,
+    RemoveObjectAt(Vector),
+    ClearObjects,
+    PlaceRamp {
+        location: Vector,
+        ramp: Ramp,
+    },
+    PlaceCharger(Vector),
+    PlaceItem {
+        location: Vector,
+        item: Item,
+    },
+    SetTerrain {
+        location: Vector,
+        terrain: Terrain,
+    },
+    PlaceTeleporters(Vector, Vector),
+    PlaceConveyor {
+        location: Vector,
+        direction: Direction,
+    },
+    PlacePatrol(Vec<Vector>),
+    /// A command to run on a later tick rather than straight away.
+    Queue(Box<Command>),
+    Tick(u32),
+    Run,
+    /// Each robot's start location and facing, and the location it has to get to.
+    PlanAll(Vec<(Vector, Direction, Vector)>),
+    Cover,
+    Analyze,
+    Stats,
+    Generate {
+        layout: Layout,
+        seed: u64,
+        connected: bool,
+    },
+    Pick,
+    Drop,
     Move,
     Rotate(RelativeDirection),
     Report,
+    Battery,
+    Inventory,
     Map,
+    MapLevel(i16),
 }
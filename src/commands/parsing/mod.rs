@@ -1,7 +1,15 @@
 use std::fmt::{Error, Formatter};
 
-use crate::commands::parsing::ParsingError::{BadPlaceParameters, UnrecognisedCommand};
+use crate::commands::parsing::ParsingError::{
+    BadChargerParameters, BadConveyorParameters, BadGenerateParameters, BadItemParameters,
+    BadMapParameters, BadObjectParameters, BadPatrolParameters, BadPlaceParameters,
+    BadPlanParameters, BadQueueParameters, BadRampParameters, BadTeleporterParameters,
+    BadTerrainParameters, BadTickParameters, UnrecognisedCommand,
+};
 use crate::commands::Command::*;
+use crate::error::Span;
+use crate::game_model::{Item, Ramp, Terrain};
+use crate::generation::Layout;
 use crate::geo::RelativeDirection::*;
 use crate::geo::{Direction, Vector};
 
@@ -11,6 +19,19 @@ use super::Command;
 pub enum ParsingError {
     UnrecognisedCommand(String),
     BadPlaceParameters(String),
+    BadObjectParameters(String),
+    BadRampParameters(String),
+    BadMapParameters(String),
+    BadChargerParameters(String),
+    BadItemParameters(String),
+    BadTerrainParameters(String),
+    BadTeleporterParameters(String),
+    BadConveyorParameters(String),
+    BadPatrolParameters(String),
+    BadQueueParameters(String),
+    BadTickParameters(String),
+    BadPlanParameters(String),
+    BadGenerateParameters(String),
 }
 
 impl std::fmt::Display for ParsingError {
@@ -18,58 +39,407 @@ impl std::fmt::Display for ParsingError {
         match self {
             UnrecognisedCommand(command) => write!(f, "Unrecognised command: {}", command),
             BadPlaceParameters(command) => write!(f, "Bad PLACE parameters: {}", command),
+            BadObjectParameters(command) => write!(f, "Bad OBJECT parameters: {}", command),
+            BadRampParameters(command) => write!(f, "Bad RAMP parameters: {}", command),
+            BadMapParameters(command) => write!(f, "Bad MAP parameters: {}", command),
+            BadChargerParameters(command) => write!(f, "Bad CHARGER parameters: {}", command),
+            BadItemParameters(command) => write!(f, "Bad ITEM parameters: {}", command),
+            BadTerrainParameters(command) => write!(f, "Bad TERRAIN parameters: {}", command),
+            BadTeleporterParameters(command) => {
+                write!(f, "Bad TELEPORTER parameters: {}", command)
+            }
+            BadConveyorParameters(command) => write!(f, "Bad CONVEYOR parameters: {}", command),
+            BadPatrolParameters(command) => write!(f, "Bad PATROL parameters: {}", command),
+            BadQueueParameters(command) => write!(f, "Bad QUEUE parameters: {}", command),
+            BadTickParameters(command) => write!(f, "Bad TICK parameters: {}", command),
+            BadPlanParameters(command) => write!(f, "Bad PLAN_ALL parameters: {}", command),
+            BadGenerateParameters(command) => write!(f, "Bad GENERATE parameters: {}", command),
         }
     }
 }
 
+impl std::error::Error for ParsingError {}
+
+/// A parsing error and the part of the input it is about. The part is always a slice of the input
+/// itself, so where it starts can be worked out from where it is in memory.
+type Rejection<'a> = (ParsingError, &'a str);
+
 pub fn parse_command(input: &str) -> Result<Command, ParsingError> {
-    let lowercase_input = input.to_lowercase();
+    parse(input).map_err(|(parsing_error, _)| parsing_error)
+}
+
+/// Parses a line of input as `parse_command` does, but fails with the span of the line the error
+/// is about as well: the field that could not be parsed, all the parameters if there is no telling
+/// which, or all of an unrecognised command.
+pub fn parse_line(input: &str) -> Result<Command, (ParsingError, Span)> {
+    parse(input).map_err(|(parsing_error, part)| {
+        let start = part.as_ptr() as usize - input.as_ptr() as usize;
+
+        (
+            parsing_error,
+            Span {
+                start,
+                end: start + part.len(),
+            },
+        )
+    })
+}
+
+fn parse(input: &str) -> Result<Command, Rejection<'_>> {
+    let lowercase_input = input.to_ascii_lowercase();
 
     match lowercase_input.as_ref() {
         "move" => return Ok(Move),
         "left" => return Ok(Rotate(Left)),
         "right" => return Ok(Rotate(Right)),
         "report" => return Ok(Report),
+        "battery" => return Ok(Battery),
+        "inventory" => return Ok(Inventory),
+        "pick" => return Ok(Pick),
+        "drop" => return Ok(Drop),
         "place_object" => return Ok(PlaceObject),
+        "remove_object" => return Ok(RemoveObject),
+        "clear_objects" => return Ok(ClearObjects),
         "map" => return Ok(Map),
+        "tick" => return Ok(Tick(1)),
+        "run" => return Ok(Run),
+        "cover" => return Ok(Cover),
+        "analyze" => return Ok(Analyze),
+        "stats" => return Ok(Stats),
         _ => {}
     }
 
     const PLACE_PREFIX: &str = "place ";
+    const OBJECT_PREFIX: &str = "object ";
+    const REMOVE_OBJECT_PREFIX: &str = "remove_object ";
+    const RAMP_PREFIX: &str = "ramp ";
+    const MAP_PREFIX: &str = "map ";
+    const CHARGER_PREFIX: &str = "charger ";
+    const ITEM_PREFIX: &str = "item ";
+    const TERRAIN_PREFIX: &str = "terrain ";
+    const TELEPORTER_PREFIX: &str = "teleporter ";
+    const CONVEYOR_PREFIX: &str = "conveyor ";
+    const PATROL_PREFIX: &str = "patrol ";
+    const QUEUE_PREFIX: &str = "queue ";
+    const TICK_PREFIX: &str = "tick ";
+    const PLAN_ALL_PREFIX: &str = "plan_all ";
+    const GENERATE_PREFIX: &str = "generate ";
+
+    if let Some(parameters) = strip_prefix_ignoring_case(input, PLACE_PREFIX) {
+        parse_place_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, OBJECT_PREFIX) {
+        parse_object_location(parameters).map(PlaceObjectAt)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, REMOVE_OBJECT_PREFIX) {
+        parse_object_location(parameters).map(RemoveObjectAt)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, RAMP_PREFIX) {
+        parse_ramp_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, MAP_PREFIX) {
+        parse_map_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, CHARGER_PREFIX) {
+        let split: Vec<&str> = parameters.split(',').collect();
 
-    if lowercase_input.starts_with(PLACE_PREFIX) {
-        parse_place_command(input[(PLACE_PREFIX.len())..].as_ref())
+        parse_location(split.as_slice())
+            .map(PlaceCharger)
+            .ok_or((BadChargerParameters(parameters.to_string()), parameters))
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, ITEM_PREFIX) {
+        parse_item_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, TERRAIN_PREFIX) {
+        parse_terrain_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, TELEPORTER_PREFIX) {
+        parse_teleporter_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, CONVEYOR_PREFIX) {
+        parse_conveyor_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, PATROL_PREFIX) {
+        parse_patrol_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, QUEUE_PREFIX) {
+        parse_queue_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, TICK_PREFIX) {
+        parameters
+            .parse::<u32>()
+            .map(Tick)
+            .map_err(|_| (BadTickParameters(parameters.to_string()), parameters))
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, PLAN_ALL_PREFIX) {
+        parse_plan_all_command(parameters)
+    } else if let Some(parameters) = strip_prefix_ignoring_case(input, GENERATE_PREFIX) {
+        parse_generate_command(parameters)
     } else {
-        Err(UnrecognisedCommand(input.to_string()))
+        Err((UnrecognisedCommand(input.to_string()), input))
+    }
+}
+
+/// The rest of `input` after `prefix`, which must be lowercase ASCII, ignoring ASCII case.
+///
+/// Only ASCII case is ignored so that the prefix is as long in `input` as it is in `prefix`, and
+/// the rest always starts on a character boundary. A full lowercase maps some non-ASCII
+/// characters, like the Kelvin sign, to ASCII letters of a different length.
+fn strip_prefix_ignoring_case<'a>(input: &'a str, prefix: &str) -> Option<&'a str> {
+    input
+        .get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| &input[prefix.len()..])
+}
+
+/// The part of `parameters` from the first of `fields` to the end of the last, where the fields
+/// were split from `parameters`.
+fn fields_within<'a>(parameters: &'a str, fields: &[&str]) -> &'a str {
+    let offset = |field: &str| field.as_ptr() as usize - parameters.as_ptr() as usize;
+
+    match (fields.first(), fields.last()) {
+        (Some(first), Some(last)) => &parameters[offset(first)..offset(last) + last.len()],
+        _ => parameters,
+    }
+}
+
+fn parse_object_location(parameters: &str) -> Result<Vector, Rejection<'_>> {
+    let split: Vec<&str> = parameters.split(',').collect();
+
+    parse_location(split.as_slice())
+        .ok_or((BadObjectParameters(parameters.to_string()), parameters))
+}
+
+fn parse_place_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    let split: Vec<&str> = parameters.split(',').collect();
+    let bad = |part| (BadPlaceParameters(parameters.to_string()), part);
+
+    match split.as_slice() {
+        [raw_location @ .., raw_direction] if !raw_location.is_empty() => {
+            let location = parse_location(raw_location)
+                .ok_or_else(|| bad(fields_within(parameters, raw_location)))?;
+
+            let facing = parse_direction(raw_direction).ok_or_else(|| bad(raw_direction))?;
+
+            Ok(Command::Place { location, facing })
+        }
+        _ => Err(bad(parameters)),
+    }
+}
+
+fn parse_ramp_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    let split: Vec<&str> = parameters.split(',').collect();
+    let bad = |part| (BadRampParameters(parameters.to_string()), part);
+
+    match split.as_slice() {
+        [raw_location @ .., raw_ramp] if !raw_location.is_empty() => {
+            let location = parse_location(raw_location)
+                .ok_or_else(|| bad(fields_within(parameters, raw_location)))?;
+
+            let ramp = match raw_ramp.to_lowercase().as_ref() {
+                "up" => Ramp::Up,
+                "down" => Ramp::Down,
+                _ => return Err(bad(raw_ramp)),
+            };
+
+            Ok(PlaceRamp { location, ramp })
+        }
+        _ => Err(bad(parameters)),
+    }
+}
+
+fn parse_item_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    let split: Vec<&str> = parameters.split(',').collect();
+    let bad = |part| (BadItemParameters(parameters.to_string()), part);
+
+    match split.as_slice() {
+        [raw_location @ .., raw_item] if !raw_location.is_empty() => {
+            let location = parse_location(raw_location)
+                .ok_or_else(|| bad(fields_within(parameters, raw_location)))?;
+
+            let mut item_chars = raw_item.chars();
+            let item = match (item_chars.next(), item_chars.next()) {
+                (Some(name), None) if Item::is_name(name.to_ascii_lowercase()) => {
+                    Item(name.to_ascii_lowercase())
+                }
+                _ => return Err(bad(raw_item)),
+            };
+
+            Ok(PlaceItem { location, item })
+        }
+        _ => Err(bad(parameters)),
+    }
+}
+
+fn parse_terrain_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    let split: Vec<&str> = parameters.split(',').collect();
+    let bad = |part| (BadTerrainParameters(parameters.to_string()), part);
+
+    match split.as_slice() {
+        [raw_location @ .., raw_terrain] if !raw_location.is_empty() => {
+            let location = parse_location(raw_location)
+                .ok_or_else(|| bad(fields_within(parameters, raw_location)))?;
+
+            let terrain = match raw_terrain.to_lowercase().as_ref() {
+                "floor" => Terrain::Floor,
+                "carpet" => Terrain::Carpet,
+                "ice" => Terrain::Ice,
+                "pit" => Terrain::Pit,
+                _ => return Err(bad(raw_terrain)),
+            };
+
+            Ok(SetTerrain { location, terrain })
+        }
+        _ => Err(bad(parameters)),
+    }
+}
+
+/// Parses the two ends of a teleporter as `X1,Y1,X2,Y2`, or `X1,Y1,Z1,X2,Y2,Z2` on a stacked
+/// board.
+fn parse_teleporter_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    let split: Vec<&str> = parameters.split(',').collect();
+    let bad = |part| (BadTeleporterParameters(parameters.to_string()), part);
+
+    match split.len() {
+        4 | 6 => {
+            let (raw_first, raw_second) = split.split_at(split.len() / 2);
+
+            let first = parse_location(raw_first)
+                .ok_or_else(|| bad(fields_within(parameters, raw_first)))?;
+            let second = parse_location(raw_second)
+                .ok_or_else(|| bad(fields_within(parameters, raw_second)))?;
+
+            Ok(PlaceTeleporters(first, second))
+        }
+        _ => Err(bad(parameters)),
     }
 }
 
-fn parse_place_command(parameters: &str) -> Result<Command, ParsingError> {
+fn parse_conveyor_command(parameters: &str) -> Result<Command, Rejection<'_>> {
     let split: Vec<&str> = parameters.split(',').collect();
+    let bad = |part| (BadConveyorParameters(parameters.to_string()), part);
 
     match split.as_slice() {
-        &[raw_x, raw_y, raw_direction] => {
-            let maybe_location: Option<Vector> = raw_x
-                .parse::<i16>()
-                .and_then(|x| raw_y.parse::<i16>().map(|y| Vector { x, y }))
-                .ok();
+        [raw_location @ .., raw_direction] if !raw_location.is_empty() => {
+            let location = parse_location(raw_location)
+                .ok_or_else(|| bad(fields_within(parameters, raw_location)))?;
 
-            let maybe_facing: Option<Direction> = parse_direction(raw_direction);
+            let direction = parse_direction(raw_direction).ok_or_else(|| bad(raw_direction))?;
 
-            maybe_location
-                .and_then(|location| maybe_facing.map(|facing| Command::Place { location, facing }))
-                .ok_or(BadPlaceParameters(parameters.to_string()))
+            Ok(PlaceConveyor {
+                location,
+                direction,
+            })
         }
-        _ => Err(BadPlaceParameters(parameters.to_string())),
+        _ => Err(bad(parameters)),
+    }
+}
+
+/// Parses a route of locations separated by spaces, such as `1,1 1,2 1,3`.
+fn parse_patrol_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    let bad = |part| (BadPatrolParameters(parameters.to_string()), part);
+
+    let route = parameters
+        .split_whitespace()
+        .map(|raw_location| {
+            let split: Vec<&str> = raw_location.split(',').collect();
+
+            parse_location(split.as_slice()).ok_or_else(|| bad(raw_location))
+        })
+        .collect::<Result<Vec<Vector>, Rejection>>()?;
+
+    if route.is_empty() {
+        return Err(bad(parameters));
+    }
+
+    Ok(PlacePatrol(route))
+}
+
+/// Parses a command to run on a later tick. Commands that run ticks themselves cannot be queued.
+fn parse_queue_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    match parse(parameters)? {
+        Queue(_) | Tick(_) | Run => Err((BadQueueParameters(parameters.to_string()), parameters)),
+        command => Ok(Queue(Box::new(command))),
     }
 }
 
-fn parse_direction(raw_direction: &str) -> Option<Direction> {
+/// Parses robots and their goals separated by semicolons, each as a start like PLACE takes and a
+/// goal location, such as `0,0,NORTH 2,2; 2,0,WEST 0,2`.
+fn parse_plan_all_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    parameters
+        .split(';')
+        .map(|raw_agent| {
+            let bad = || (BadPlanParameters(parameters.to_string()), raw_agent.trim());
+
+            match *raw_agent.split_whitespace().collect::<Vec<&str>>() {
+                [raw_start, raw_goal] => {
+                    let goal_split: Vec<&str> = raw_goal.split(',').collect();
+
+                    match (parse_place_command(raw_start), parse_location(&goal_split)) {
+                        (Ok(Place { location, facing }), Some(goal)) => {
+                            Ok((location, facing, goal))
+                        }
+                        _ => Err(bad()),
+                    }
+                }
+                _ => Err(bad()),
+            }
+        })
+        .collect::<Result<Vec<(Vector, Direction, Vector)>, Rejection>>()
+        .map(PlanAll)
+}
+
+/// Parses `RANDOM,DENSITY,SEED`, `MAZE,SEED` or `ROOMS,SEED`, each optionally followed by
+/// `,CONNECTED`.
+fn parse_generate_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    let lowercase_parameters = parameters.to_lowercase();
+    let split: Vec<&str> = lowercase_parameters.split(',').collect();
+
+    let (raw_layout, connected) = match split.as_slice() {
+        [raw_layout @ .., "connected"] => (raw_layout, true),
+        raw_layout => (raw_layout, false),
+    };
+
+    let maybe_layout: Option<(Layout, &str)> = match *raw_layout {
+        ["random", raw_density, raw_seed] => raw_density
+            .parse::<u8>()
+            .ok()
+            .filter(|density| *density <= 100)
+            .map(|density| (Layout::Random { density }, raw_seed)),
+        ["maze", raw_seed] => Some((Layout::Maze, raw_seed)),
+        ["rooms", raw_seed] => Some((Layout::Rooms, raw_seed)),
+        _ => None,
+    };
+
+    maybe_layout
+        .and_then(|(layout, raw_seed)| {
+            raw_seed.parse::<u64>().ok().map(|seed| Generate {
+                layout,
+                seed,
+                connected,
+            })
+        })
+        .ok_or((BadGenerateParameters(parameters.to_string()), parameters))
+}
+
+fn parse_map_command(parameters: &str) -> Result<Command, Rejection<'_>> {
+    parameters
+        .parse::<i16>()
+        .map(MapLevel)
+        .map_err(|_| (BadMapParameters(parameters.to_string()), parameters))
+}
+
+/// Parses `X,Y` on the ground level or `X,Y,Z` on a stacked board.
+pub(crate) fn parse_location(raw_coordinates: &[&str]) -> Option<Vector> {
+    let coordinates: Vec<i16> = raw_coordinates
+        .iter()
+        .map(|raw| raw.parse::<i16>().ok())
+        .collect::<Option<Vec<i16>>>()?;
+
+    match *coordinates.as_slice() {
+        [x, y] => Some(Vector::new(x, y)),
+        [x, y, z] => Some(Vector::at_level(x, y, z)),
+        _ => None,
+    }
+}
+
+pub(crate) fn parse_direction(raw_direction: &str) -> Option<Direction> {
     match raw_direction.to_lowercase().as_ref() {
         "north" => Some(Direction::North),
         "south" => Some(Direction::South),
         "east" => Some(Direction::East),
         "west" => Some(Direction::West),
+        "northeast" => Some(Direction::NorthEast),
+        "northwest" => Some(Direction::NorthWest),
+        "southeast" => Some(Direction::SouthEast),
+        "southwest" => Some(Direction::SouthWest),
         _ => None,
     }
 }
@@ -79,6 +449,8 @@ mod test {
     use crate::commands::parsing::parse_command;
     use crate::commands::parsing::ParsingError::*;
     use crate::commands::Command::*;
+    use crate::game_model::{Item, Ramp, Terrain};
+    use crate::generation::Layout;
     use crate::geo::Direction::*;
     use crate::geo::RelativeDirection::*;
     use crate::geo::Vector;
@@ -103,11 +475,333 @@ mod test {
         assert_eq!(parse_command("Report"), Ok(Report))
     }
 
+    #[test]
+    fn parse_battery() {
+        assert_eq!(parse_command("Battery"), Ok(Battery))
+    }
+
+    #[test]
+    fn parse_charger() {
+        assert_eq!(
+            parse_command("Charger 2,3"),
+            Ok(PlaceCharger(Vector::new(2, 3)))
+        )
+    }
+
+    #[test]
+    fn parse_charger_bad_coordinate() {
+        assert_eq!(
+            parse_command("Charger 2,here"),
+            Err(BadChargerParameters("2,here".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_pick() {
+        assert_eq!(parse_command("Pick"), Ok(Pick))
+    }
+
+    #[test]
+    fn parse_drop() {
+        assert_eq!(parse_command("Drop"), Ok(Drop))
+    }
+
+    #[test]
+    fn parse_inventory() {
+        assert_eq!(parse_command("Inventory"), Ok(Inventory))
+    }
+
+    #[test]
+    fn parse_item() {
+        assert_eq!(
+            parse_command("Item 2,3,B"),
+            Ok(PlaceItem {
+                location: Vector::new(2, 3),
+                item: Item('b')
+            })
+        )
+    }
+
+    #[test]
+    fn parse_item_bad_name() {
+        assert_eq!(
+            parse_command("Item 2,3,box"),
+            Err(BadItemParameters("2,3,box".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_item_named_like_a_robot() {
+        assert_eq!(
+            parse_command("Item 2,3,V"),
+            Err(BadItemParameters("2,3,V".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_terrain() {
+        assert_eq!(
+            parse_command("Terrain 2,3,Ice"),
+            Ok(SetTerrain {
+                location: Vector::new(2, 3),
+                terrain: Terrain::Ice
+            })
+        )
+    }
+
+    #[test]
+    fn parse_terrain_unknown() {
+        assert_eq!(
+            parse_command("Terrain 2,3,Lava"),
+            Err(BadTerrainParameters("2,3,Lava".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_teleporter() {
+        assert_eq!(
+            parse_command("Teleporter 0,1,3,4"),
+            Ok(PlaceTeleporters(Vector::new(0, 1), Vector::new(3, 4)))
+        )
+    }
+
+    #[test]
+    fn parse_teleporter_across_levels() {
+        assert_eq!(
+            parse_command("Teleporter 0,1,0,3,4,2"),
+            Ok(PlaceTeleporters(
+                Vector::new(0, 1),
+                Vector::at_level(3, 4, 2)
+            ))
+        )
+    }
+
+    #[test]
+    fn parse_teleporter_one_end() {
+        assert_eq!(
+            parse_command("Teleporter 0,1"),
+            Err(BadTeleporterParameters("0,1".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_conveyor() {
+        assert_eq!(
+            parse_command("Conveyor 2,3,West"),
+            Ok(PlaceConveyor {
+                location: Vector::new(2, 3),
+                direction: West
+            })
+        )
+    }
+
+    #[test]
+    fn parse_conveyor_bad_direction() {
+        assert_eq!(
+            parse_command("Conveyor 2,3,Up"),
+            Err(BadConveyorParameters("2,3,Up".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_patrol() {
+        assert_eq!(
+            parse_command("Patrol 1,1 1,2 2,2"),
+            Ok(PlacePatrol(vec![
+                Vector::new(1, 1),
+                Vector::new(1, 2),
+                Vector::new(2, 2)
+            ]))
+        )
+    }
+
+    #[test]
+    fn parse_patrol_bad_location() {
+        assert_eq!(
+            parse_command("Patrol 1,1 1"),
+            Err(BadPatrolParameters("1,1 1".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_queue() {
+        assert_eq!(parse_command("Queue Move"), Ok(Queue(Box::new(Move))))
+    }
+
+    #[test]
+    fn parse_queue_tick() {
+        assert_eq!(
+            parse_command("Queue Tick 2"),
+            Err(BadQueueParameters("Tick 2".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_tick() {
+        assert_eq!(parse_command("Tick"), Ok(Tick(1)))
+    }
+
+    #[test]
+    fn parse_tick_count() {
+        assert_eq!(parse_command("Tick 5"), Ok(Tick(5)))
+    }
+
+    #[test]
+    fn parse_tick_bad_count() {
+        assert_eq!(
+            parse_command("Tick -1"),
+            Err(BadTickParameters("-1".to_string()))
+        )
+    }
+
+    // Found by fuzzing: the Kelvin sign lowercases to an ASCII `k` one byte long, so this once
+    // matched "tick " and then sliced the input partway through the sign.
+    #[test]
+    fn parse_kelvin_sign_is_not_tick() {
+        assert_eq!(
+            parse_command("TIC\u{212A} 1"),
+            Err(UnrecognisedCommand("TIC\u{212A} 1".to_string()))
+        );
+        assert_eq!(
+            parse_command("tic\u{212A}"),
+            Err(UnrecognisedCommand("tic\u{212A}".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_place_with_non_ascii_parameters() {
+        assert_eq!(
+            parse_command("PLACE ẞ,İ,NORTH"),
+            Err(BadPlaceParameters("ẞ,İ,NORTH".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_cover() {
+        assert_eq!(parse_command("Cover"), Ok(Cover))
+    }
+
+    #[test]
+    fn parse_analyze() {
+        assert_eq!(parse_command("Analyze"), Ok(Analyze))
+    }
+
+    #[test]
+    fn parse_stats() {
+        assert_eq!(parse_command("STATS"), Ok(Stats))
+    }
+
+    #[test]
+    fn parse_generate_random() {
+        assert_eq!(
+            parse_command("Generate Random,30,42"),
+            Ok(Generate {
+                layout: Layout::Random { density: 30 },
+                seed: 42,
+                connected: false
+            })
+        )
+    }
+
+    #[test]
+    fn parse_generate_connected_maze() {
+        assert_eq!(
+            parse_command("Generate Maze,7,Connected"),
+            Ok(Generate {
+                layout: Layout::Maze,
+                seed: 7,
+                connected: true
+            })
+        )
+    }
+
+    #[test]
+    fn parse_generate_density_over_100() {
+        assert_eq!(
+            parse_command("Generate Random,130,42"),
+            Err(BadGenerateParameters("Random,130,42".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_generate_without_seed() {
+        assert_eq!(
+            parse_command("Generate Rooms"),
+            Err(BadGenerateParameters("Rooms".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_run() {
+        assert_eq!(parse_command("Run"), Ok(Run))
+    }
+
+    #[test]
+    fn parse_plan_all() {
+        assert_eq!(
+            parse_command("Plan_All 0,0,North 2,2; 2,0,West 0,2"),
+            Ok(PlanAll(vec![
+                (Vector::new(0, 0), North, Vector::new(2, 2)),
+                (Vector::new(2, 0), West, Vector::new(0, 2)),
+            ]))
+        )
+    }
+
+    #[test]
+    fn parse_plan_all_without_goal() {
+        assert_eq!(
+            parse_command("Plan_All 0,0,North"),
+            Err(BadPlanParameters("0,0,North".to_string()))
+        )
+    }
+
     #[test]
     fn parse_place_object() {
         assert_eq!(parse_command("Place_Object"), Ok(PlaceObject))
     }
 
+    #[test]
+    fn parse_remove_object() {
+        assert_eq!(parse_command("Remove_Object"), Ok(RemoveObject))
+    }
+
+    #[test]
+    fn parse_clear_objects() {
+        assert_eq!(parse_command("Clear_Objects"), Ok(ClearObjects))
+    }
+
+    #[test]
+    fn parse_object_at() {
+        assert_eq!(
+            parse_command("Object 2,3"),
+            Ok(PlaceObjectAt(Vector::new(2, 3)))
+        )
+    }
+
+    #[test]
+    fn parse_remove_object_at() {
+        assert_eq!(
+            parse_command("Remove_Object 2,3"),
+            Ok(RemoveObjectAt(Vector::new(2, 3)))
+        )
+    }
+
+    #[test]
+    fn parse_object_at_wrong_num_args() {
+        assert_eq!(
+            parse_command("Object 1,2,3,4"),
+            Err(BadObjectParameters("1,2,3,4".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_remove_object_at_bad_coordinate() {
+        assert_eq!(
+            parse_command("Remove_Object 1,ASDF"),
+            Err(BadObjectParameters("1,ASDF".to_string()))
+        )
+    }
+
     #[test]
     fn parse_map() {
         assert_eq!(parse_command("Map"), Ok(Map))
@@ -134,7 +828,7 @@ mod test {
         assert_eq!(
             parse_command("Place 1,1,North"),
             Ok(Place {
-                location: Vector { x: 1, y: 1 },
+                location: Vector::new(1, 1),
                 facing: North
             })
         )
@@ -145,7 +839,7 @@ mod test {
         assert_eq!(
             parse_command("Place 1,1,South"),
             Ok(Place {
-                location: Vector { x: 1, y: 1 },
+                location: Vector::new(1, 1),
                 facing: South
             })
         )
@@ -156,7 +850,7 @@ mod test {
         assert_eq!(
             parse_command("Place 1,1,East"),
             Ok(Place {
-                location: Vector { x: 1, y: 1 },
+                location: Vector::new(1, 1),
                 facing: East
             })
         )
@@ -167,12 +861,104 @@ mod test {
         assert_eq!(
             parse_command("Place 1,1,West"),
             Ok(Place {
-                location: Vector { x: 1, y: 1 },
+                location: Vector::new(1, 1),
                 facing: West
             })
         )
     }
 
+    #[test]
+    fn parse_place_facing_north_east() {
+        assert_eq!(
+            parse_command("Place 1,1,NorthEast"),
+            Ok(Place {
+                location: Vector::new(1, 1),
+                facing: NorthEast
+            })
+        )
+    }
+
+    #[test]
+    fn parse_place_facing_south_west() {
+        assert_eq!(
+            parse_command("Place 1,1,SOUTHWEST"),
+            Ok(Place {
+                location: Vector::new(1, 1),
+                facing: SouthWest
+            })
+        )
+    }
+
+    #[test]
+    fn parse_place_on_level() {
+        assert_eq!(
+            parse_command("Place 1,1,2,North"),
+            Ok(Place {
+                location: Vector::at_level(1, 1, 2),
+                facing: North
+            })
+        )
+    }
+
+    #[test]
+    fn parse_place_too_many_args() {
+        assert_eq!(
+            parse_command("Place 1,1,2,3,North"),
+            Err(BadPlaceParameters("1,1,2,3,North".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_object_at_on_level() {
+        assert_eq!(
+            parse_command("Object 2,3,1"),
+            Ok(PlaceObjectAt(Vector::at_level(2, 3, 1)))
+        )
+    }
+
+    #[test]
+    fn parse_ramp_up() {
+        assert_eq!(
+            parse_command("Ramp 2,3,0,Up"),
+            Ok(PlaceRamp {
+                location: Vector::new(2, 3),
+                ramp: Ramp::Up
+            })
+        )
+    }
+
+    #[test]
+    fn parse_ramp_down() {
+        assert_eq!(
+            parse_command("Ramp 2,3,1,Down"),
+            Ok(PlaceRamp {
+                location: Vector::at_level(2, 3, 1),
+                ramp: Ramp::Down
+            })
+        )
+    }
+
+    #[test]
+    fn parse_ramp_bad_direction() {
+        assert_eq!(
+            parse_command("Ramp 2,3,1,Sideways"),
+            Err(BadRampParameters("2,3,1,Sideways".to_string()))
+        )
+    }
+
+    #[test]
+    fn parse_map_level() {
+        assert_eq!(parse_command("Map 2"), Ok(MapLevel(2)))
+    }
+
+    #[test]
+    fn parse_map_bad_level() {
+        assert_eq!(
+            parse_command("Map top"),
+            Err(BadMapParameters("top".to_string()))
+        )
+    }
+
     #[test]
     fn parse_unrecognised() {
         assert_eq!(
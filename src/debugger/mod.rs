@@ -0,0 +1,425 @@
+use std::fmt::{Error, Formatter};
+
+use crate::commands::parsing::parse_location;
+use crate::commands::Command;
+use crate::debugger::Breakpoint::{Line, Rejected, RobotAt};
+use crate::debugger::DebugCommand::*;
+use crate::debugger::DebugError::{NoSuchBreakpoint, NoSuchLine, UnrecognisedDebugCommand};
+use crate::game_execution::output_from_command;
+use crate::game_model::Board;
+use crate::geo::Vector;
+use crate::session::{run_line, Entry};
+
+/// Where `CONTINUE` and `REVERSE` should stop.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Breakpoint {
+    /// Once the given script line has run, counting from 1.
+    Line(usize),
+    RobotAt(Vector),
+    /// After any command that was rejected, like a move off the board.
+    Rejected,
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            Line(line) => write!(f, "line {}", line),
+            RobotAt(location) => write!(f, "robot at {}", location),
+            Rejected => write!(f, "rejected command"),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum DebugCommand {
+    Step(usize),
+    Back(usize),
+    Jump(usize),
+    Continue,
+    Reverse,
+    Break(Breakpoint),
+    Delete(usize),
+    Breakpoints,
+    Show,
+    Quit,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum DebugError {
+    UnrecognisedDebugCommand(String),
+    NoSuchLine(usize),
+    NoSuchBreakpoint(usize),
+}
+
+impl std::fmt::Display for DebugError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            UnrecognisedDebugCommand(command) => {
+                write!(f, "Unrecognised debug command: {}", command)
+            }
+            NoSuchLine(line) => write!(f, "No such script line: {}", line),
+            NoSuchBreakpoint(number) => write!(f, "No such breakpoint: {}", number),
+        }
+    }
+}
+
+impl std::error::Error for DebugError {}
+
+/// Parses `STEP [n]`, `BACK [n]`, `JUMP <line>`, `CONTINUE`, `REVERSE`, `BREAK <line>`,
+/// `BREAK AT X,Y[,Z]`, `BREAK REJECTED`, `DELETE <n>`, `BREAKPOINTS`, `SHOW` and `QUIT`.
+pub fn parse_debug_command(input: &str) -> Result<DebugCommand, DebugError> {
+    let unrecognised = || UnrecognisedDebugCommand(input.to_string());
+    let words: Vec<String> = input.split_whitespace().map(str::to_lowercase).collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let number = |raw: &str| raw.parse::<usize>().map_err(|_| unrecognised());
+
+    match words.as_slice() {
+        ["step"] => Ok(Step(1)),
+        ["step", count] => number(count).map(Step),
+        ["back"] => Ok(Back(1)),
+        ["back", count] => number(count).map(Back),
+        ["jump", line] => number(line).map(Jump),
+        ["continue"] => Ok(Continue),
+        ["reverse"] => Ok(Reverse),
+        ["break", "rejected"] => Ok(Break(Rejected)),
+        ["break", "at", location] => {
+            let split: Vec<&str> = location.split(',').collect();
+
+            parse_location(split.as_slice())
+                .map(|location| Break(RobotAt(location)))
+                .ok_or_else(unrecognised)
+        }
+        ["break", line] => number(line).map(|line| Break(Line(line))),
+        ["delete", breakpoint] => number(breakpoint).map(Delete),
+        ["breakpoints"] => Ok(Breakpoints),
+        ["show"] => Ok(Show),
+        ["quit"] => Ok(Quit),
+        _ => Err(unrecognised()),
+    }
+}
+
+/// A script that has already been run, with the board kept after every line so it can be
+/// stepped through in either direction.
+pub struct Debugger {
+    /// The board before the script, then after each line.
+    snapshots: Vec<Board>,
+    entries: Vec<Entry>,
+    /// How many script lines have run.
+    position: usize,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Debugger {
+    pub fn new(board: &Board, script: &str) -> Debugger {
+        let mut snapshots = vec![board.clone()];
+        let mut entries = Vec::new();
+
+        for line in script.lines() {
+            let (next_board, entry, _) = run_line(snapshots.last().unwrap(), line);
+            snapshots.push(next_board);
+            entries.push(entry);
+        }
+
+        Debugger {
+            snapshots,
+            entries,
+            position: 0,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.snapshots[self.position]
+    }
+
+    /// Carries out the debug command, returning what to show. `QUIT` is left to the caller.
+    pub fn execute(&mut self, command: &DebugCommand) -> Result<String, DebugError> {
+        let last = self.entries.len();
+
+        match command {
+            Step(count) => {
+                self.position = self.position.saturating_add(*count).min(last);
+                Ok(self.stop(None))
+            }
+            Back(count) => {
+                self.position = self.position.saturating_sub(*count);
+                Ok(self.stop(None))
+            }
+            Jump(line) if *line > last => Err(NoSuchLine(*line)),
+            Jump(line) => {
+                self.position = *line;
+                Ok(self.stop(None))
+            }
+            Continue => {
+                let hit = (self.position + 1..=last).find_map(|position| {
+                    self.breakpoint_at(position)
+                        .map(|breakpoint| (position, breakpoint))
+                });
+                Ok(self.stop_at(hit, last))
+            }
+            Reverse => {
+                let hit = (0..self.position).rev().find_map(|position| {
+                    self.breakpoint_at(position)
+                        .map(|breakpoint| (position, breakpoint))
+                });
+                Ok(self.stop_at(hit, 0))
+            }
+            Break(breakpoint) => {
+                self.breakpoints.push(breakpoint.clone());
+                Ok(format!(
+                    "Breakpoint {}: {}",
+                    self.breakpoints.len(),
+                    breakpoint
+                ))
+            }
+            Delete(number) if *number == 0 || *number > self.breakpoints.len() => {
+                Err(NoSuchBreakpoint(*number))
+            }
+            Delete(number) => Ok(format!(
+                "Deleted breakpoint {}: {}",
+                number,
+                self.breakpoints.remove(number - 1)
+            )),
+            Breakpoints if self.breakpoints.is_empty() => Ok("No breakpoints".to_string()),
+            Breakpoints => Ok(self
+                .breakpoints
+                .iter()
+                .enumerate()
+                .map(|(index, breakpoint)| format!("{}: {}", index + 1, breakpoint))
+                .collect::<Vec<String>>()
+                .join("\n")),
+            Show => Ok(self.stop(None)),
+            Quit => Ok(String::new()),
+        }
+    }
+
+    fn breakpoint_at(&self, position: usize) -> Option<Breakpoint> {
+        let entry = position.checked_sub(1).map(|index| &self.entries[index]);
+
+        self.breakpoints
+            .iter()
+            .find(|breakpoint| match breakpoint {
+                Line(line) => *line == position,
+                RobotAt(location) => self.snapshots[position]
+                    .robot
+                    .as_ref()
+                    .is_some_and(|robot| robot.location == *location),
+                Rejected => entry.is_some_and(|entry| entry.error.is_some()),
+            })
+            .cloned()
+    }
+
+    fn stop_at(&mut self, hit: Option<(usize, Breakpoint)>, otherwise: usize) -> String {
+        match hit {
+            Some((position, breakpoint)) => {
+                self.position = position;
+                self.stop(Some(&breakpoint))
+            }
+            None => {
+                self.position = otherwise;
+                self.stop(None)
+            }
+        }
+    }
+
+    /// The line just run, what it printed, and the MAP as it stands.
+    fn stop(&self, breakpoint: Option<&Breakpoint>) -> String {
+        let mut lines: Vec<String> = Vec::new();
+
+        match self
+            .position
+            .checked_sub(1)
+            .map(|index| &self.entries[index])
+        {
+            None => lines.push("Start of script".to_string()),
+            Some(entry) => {
+                lines.push(format!("Line {}: {}", self.position, entry.input));
+
+                if let Err(parsing_error) = &entry.command {
+                    lines.push(format!("Error: {}", parsing_error));
+                }
+                if let Some(output) = &entry.output {
+                    lines.push(output.clone());
+                }
+                if let Some(error) = &entry.error {
+                    lines.push(format!("Error: {}", error));
+                }
+            }
+        }
+
+        if self.position == self.entries.len() {
+            lines.push("End of script".to_string());
+        }
+
+        if let Some(breakpoint) = breakpoint {
+            lines.push(format!("Stopped at {}", breakpoint));
+        }
+
+        lines.extend(output_from_command(self.board(), &Command::Map));
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debugger::Breakpoint::{Line, Rejected, RobotAt};
+    use crate::debugger::DebugCommand::*;
+    use crate::debugger::DebugError::{NoSuchBreakpoint, NoSuchLine, UnrecognisedDebugCommand};
+    use crate::debugger::{parse_debug_command, Debugger};
+    use crate::game_model::Board;
+    use crate::geo::Vector;
+
+    const SCRIPT: &str = "PLACE 0,0,EAST\nMOVE\nMOVE\nLEFT\nMOVE\nRIGHT\nMOVE\nMOVE\nMOVE";
+
+    fn debugger() -> Debugger {
+        Debugger::new(&Board::empty_with_corner(&Vector::new(4, 4)), SCRIPT)
+    }
+
+    fn location(debugger: &Debugger) -> Option<Vector> {
+        debugger.board().robot.as_ref().map(|robot| robot.location)
+    }
+
+    #[test]
+    fn parse_debug_commands() {
+        assert_eq!(Ok(Step(1)), parse_debug_command("STEP"));
+        assert_eq!(Ok(Back(3)), parse_debug_command("back 3"));
+        assert_eq!(Ok(Jump(4)), parse_debug_command("JUMP 4"));
+        assert_eq!(Ok(Break(Line(2))), parse_debug_command("BREAK 2"));
+        assert_eq!(
+            Ok(Break(RobotAt(Vector::new(2, 3)))),
+            parse_debug_command("BREAK AT 2,3")
+        );
+        assert_eq!(Ok(Break(Rejected)), parse_debug_command("BREAK REJECTED"));
+        assert_eq!(
+            Err(UnrecognisedDebugCommand("STEP -1".to_string())),
+            parse_debug_command("STEP -1")
+        );
+    }
+
+    #[test]
+    fn step_forward_and_back() {
+        let mut debugger = debugger();
+
+        debugger.execute(&Step(3)).unwrap();
+        assert_eq!(Some(Vector::new(2, 0)), location(&debugger));
+
+        debugger.execute(&Back(1)).unwrap();
+        assert_eq!(Some(Vector::new(1, 0)), location(&debugger));
+
+        debugger.execute(&Back(5)).unwrap();
+        assert_eq!(0, debugger.position());
+        assert_eq!(None, location(&debugger));
+    }
+
+    #[test]
+    fn step_past_the_end() {
+        let mut debugger = debugger();
+        debugger.execute(&Step(2)).unwrap();
+
+        debugger
+            .execute(&parse_debug_command("STEP 18446744073709551615").unwrap())
+            .unwrap();
+
+        assert_eq!(9, debugger.position());
+        assert_eq!(Some(Vector::new(4, 1)), location(&debugger));
+    }
+
+    #[test]
+    fn jump_to_line() {
+        let mut debugger = debugger();
+
+        debugger.execute(&Jump(5)).unwrap();
+
+        assert_eq!(Some(Vector::new(2, 1)), location(&debugger));
+        assert_eq!(Err(NoSuchLine(10)), debugger.execute(&Jump(10)));
+    }
+
+    #[test]
+    fn stop_shows_line_and_map() {
+        let mut debugger = debugger();
+
+        assert_eq!(
+            Ok("Line 2: MOVE\n00000\n00000\n00000\n00000\n0>000".to_string()),
+            debugger.execute(&Step(2))
+        );
+    }
+
+    #[test]
+    fn continue_to_breakpoint() {
+        let mut debugger = debugger();
+
+        debugger.execute(&Break(Line(4))).unwrap();
+        let output = debugger.execute(&Continue).unwrap();
+
+        assert_eq!(4, debugger.position());
+        assert!(output.contains("Stopped at line 4"));
+    }
+
+    #[test]
+    fn continue_to_robot_location() {
+        let mut debugger = debugger();
+
+        debugger
+            .execute(&Break(RobotAt(Vector::new(3, 1))))
+            .unwrap();
+        debugger.execute(&Continue).unwrap();
+
+        assert_eq!(7, debugger.position());
+    }
+
+    #[test]
+    fn continue_to_rejected_command() {
+        let mut debugger = debugger();
+
+        debugger.execute(&Break(Rejected)).unwrap();
+        let output = debugger.execute(&Continue).unwrap();
+
+        assert_eq!(9, debugger.position());
+        assert!(output.contains("Error: Location is out of bounds: 5,1"));
+    }
+
+    #[test]
+    fn continue_without_breakpoint() {
+        let mut debugger = debugger();
+
+        let output = debugger.execute(&Continue).unwrap();
+
+        assert_eq!(9, debugger.position());
+        assert!(output.contains("End of script"));
+    }
+
+    #[test]
+    fn reverse_to_breakpoint() {
+        let mut debugger = debugger();
+
+        debugger
+            .execute(&Break(RobotAt(Vector::new(1, 0))))
+            .unwrap();
+        debugger.execute(&Jump(8)).unwrap();
+        debugger.execute(&Reverse).unwrap();
+
+        assert_eq!(2, debugger.position());
+    }
+
+    #[test]
+    fn delete_breakpoint() {
+        let mut debugger = debugger();
+
+        debugger.execute(&Break(Line(4))).unwrap();
+
+        assert_eq!(
+            Ok("Deleted breakpoint 1: line 4".to_string()),
+            debugger.execute(&Delete(1))
+        );
+        assert_eq!(Err(NoSuchBreakpoint(1)), debugger.execute(&Delete(1)));
+        assert_eq!(
+            Ok("No breakpoints".to_string()),
+            debugger.execute(&Breakpoints)
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt::{Error, Formatter};
+use std::io;
+
+use crate::commands::parsing::ParsingError;
+use crate::error::ConfigError::{
+    BadFlagValue, BadLevel, BadRecording, MissingFlagValue, NoSuchBuiltinLevel,
+};
+use crate::error::RobotError::{Config, Diverged, Execution, Io, Parse, ScenariosFailed};
+use crate::game_execution::ExecutionError;
+use crate::puzzle::LevelError;
+use crate::session::{Divergence, SessionError};
+
+/// Where in a line of input something went wrong, as byte offsets from its start.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Anything that can go wrong driving the robot, from reading files and flags through parsing
+/// commands to running them.
+#[derive(Debug)]
+pub enum RobotError {
+    Parse {
+        input: String,
+        span: Span,
+        error: ParsingError,
+    },
+    Execution(ExecutionError),
+    Io {
+        path: String,
+        error: io::Error,
+    },
+    Config(ConfigError),
+    /// A replayed recording did something different from what was recorded.
+    Diverged(Divergence),
+    /// How many of the scenarios run failed, when any did.
+    ScenariosFailed(usize),
+}
+
+impl RobotError {
+    /// A parsing error with the span of `input` it is about.
+    pub fn parsing(input: &str, span: Span, error: ParsingError) -> RobotError {
+        Parse {
+            input: input.to_string(),
+            span,
+            error,
+        }
+    }
+
+    pub fn io(path: &str, error: io::Error) -> RobotError {
+        Io {
+            path: path.to_string(),
+            error,
+        }
+    }
+}
+
+impl std::fmt::Display for RobotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            Parse { input, span, error } => {
+                let before = input.get(..span.start).unwrap_or("").chars().count();
+                let within = input
+                    .get(span.start..span.end)
+                    .unwrap_or("")
+                    .chars()
+                    .count();
+
+                write!(
+                    f,
+                    "{}\n  {}\n  {}{}",
+                    error,
+                    input,
+                    " ".repeat(before),
+                    "^".repeat(within.max(1))
+                )
+            }
+            Execution(error) => write!(f, "{}", error),
+            Io { path, error } => write!(f, "{}: {}", path, error),
+            Config(error) => write!(f, "{}", error),
+            Diverged(divergence) => write!(f, "{}", divergence),
+            ScenariosFailed(failed) => write!(f, "Scenarios failed: {}", failed),
+        }
+    }
+}
+
+impl std::error::Error for RobotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Parse { error, .. } => Some(error),
+            Execution(error) => Some(error),
+            Io { error, .. } => Some(error),
+            Config(error) => Some(error),
+            Diverged(divergence) => Some(divergence),
+            ScenariosFailed(_) => None,
+        }
+    }
+}
+
+impl From<ExecutionError> for RobotError {
+    fn from(error: ExecutionError) -> RobotError {
+        Execution(error)
+    }
+}
+
+impl From<ConfigError> for RobotError {
+    fn from(error: ConfigError) -> RobotError {
+        Config(error)
+    }
+}
+
+impl From<Divergence> for RobotError {
+    fn from(divergence: Divergence) -> RobotError {
+        Diverged(divergence)
+    }
+}
+
+/// Problems with how the program was set up: its flags, and the level or recording it was given.
+#[derive(Debug, Eq, PartialEq)]
+pub enum ConfigError {
+    MissingFlagValue(String),
+    BadFlagValue { flag: String, value: String },
+    NoSuchBuiltinLevel(usize),
+    BadLevel(LevelError),
+    BadRecording(SessionError),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            MissingFlagValue(flag) => write!(f, "Missing value for {}", flag),
+            BadFlagValue { flag, value } => write!(f, "Bad value for {}: {}", flag, value),
+            NoSuchBuiltinLevel(number) => write!(f, "No built-in level {}", number),
+            BadLevel(error) => write!(f, "{}", error),
+            BadRecording(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BadLevel(error) => Some(error),
+            BadRecording(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<LevelError> for ConfigError {
+    fn from(error: LevelError) -> ConfigError {
+        BadLevel(error)
+    }
+}
+
+impl From<SessionError> for ConfigError {
+    fn from(error: SessionError) -> ConfigError {
+        BadRecording(error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+
+    use crate::commands::parsing::parse_line;
+    use crate::commands::parsing::ParsingError::{BadPlaceParameters, UnrecognisedCommand};
+    use crate::error::ConfigError::{BadLevel, NoSuchBuiltinLevel};
+    use crate::error::RobotError::{Config, Diverged, Execution, Parse, ScenariosFailed};
+    use crate::error::{RobotError, Span};
+    use crate::game_execution::ExecutionError::OutOfBounds;
+    use crate::geo::Vector;
+    use crate::puzzle::LevelError::MissingMap;
+    use crate::session::Divergence;
+
+    fn parse_error(input: &str) -> RobotError {
+        let (parsing_error, span) = parse_line(input).unwrap_err();
+
+        RobotError::parsing(input, span, parsing_error)
+    }
+
+    #[test]
+    fn parsing_spans_the_bad_field() {
+        match parse_error("PLACE 1,2,UP") {
+            Parse { span, error, .. } => {
+                assert_eq!(Span { start: 10, end: 12 }, span);
+                assert_eq!(BadPlaceParameters("1,2,UP".to_string()), error);
+            }
+            error => panic!("Expected a parse error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn parsing_spans_a_bad_location() {
+        match parse_error("PLACE 1,x,NORTH") {
+            Parse { span, .. } => assert_eq!(Span { start: 6, end: 9 }, span),
+            error => panic!("Expected a parse error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn parsing_spans_a_bad_patrol_stop() {
+        match parse_error("PATROL 1,1 x 2,2") {
+            Parse { span, .. } => assert_eq!(Span { start: 11, end: 12 }, span),
+            error => panic!("Expected a parse error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn parsing_spans_an_unrecognised_command() {
+        match parse_error("JUMP") {
+            Parse { span, error, .. } => {
+                assert_eq!(Span { start: 0, end: 4 }, span);
+                assert_eq!(UnrecognisedCommand("JUMP".to_string()), error);
+            }
+            error => panic!("Expected a parse error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn parsing_spans_a_queued_command() {
+        match parse_error("QUEUE PLACE 1,2,UP") {
+            Parse { span, .. } => assert_eq!(Span { start: 16, end: 18 }, span),
+            error => panic!("Expected a parse error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn parsing_displays_the_span() {
+        assert_eq!(
+            "Bad PLACE parameters: 1,2,UP\n  PLACE 1,2,UP\n            ^^",
+            parse_error("PLACE 1,2,UP").to_string()
+        )
+    }
+
+    #[test]
+    fn parsing_displays_an_empty_span() {
+        assert_eq!(
+            "Bad PLACE parameters: \n  PLACE \n        ^",
+            parse_error("PLACE ").to_string()
+        )
+    }
+
+    #[test]
+    fn execution_from_its_error() {
+        let error: RobotError = OutOfBounds(Vector::new(5, 5)).into();
+
+        assert!(matches!(error, Execution(OutOfBounds(_))));
+        assert_eq!("Location is out of bounds: 5,5", error.to_string());
+    }
+
+    #[test]
+    fn config_with_its_source() {
+        let error: RobotError = Config(BadLevel(MissingMap));
+
+        assert_eq!("Level has no MAP section", error.to_string());
+        assert_eq!(
+            Some("Level has no MAP section".to_string()),
+            error
+                .source()
+                .and_then(Error::source)
+                .map(ToString::to_string)
+        );
+    }
+
+    #[test]
+    fn config_without_a_source() {
+        let error: RobotError = Config(NoSuchBuiltinLevel(9));
+
+        assert_eq!("No built-in level 9", error.to_string());
+        assert!(error.source().unwrap().source().is_none());
+    }
+
+    #[test]
+    fn diverged_from_the_divergence() {
+        let error: RobotError = Divergence {
+            line: 2,
+            input: "REPORT".to_string(),
+            field: "output",
+            expected: "0,1,NORTH".to_string(),
+            actual: "0,0,NORTH".to_string(),
+        }
+        .into();
+
+        assert!(matches!(error, Diverged(_)));
+        assert!(error.source().is_some());
+        assert_eq!(
+            "Diverged at input 2 (REPORT): output was\n  0,1,NORTH\nbut is now\n  0,0,NORTH",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn scenarios_failed_with_the_count() {
+        assert_eq!("Scenarios failed: 2", ScenariosFailed(2).to_string());
+    }
+
+    #[test]
+    fn io_with_the_path() {
+        let error = RobotError::io(
+            "missing.level",
+            std::io::Error::new(std::io::ErrorKind::NotFound, "not found"),
+        );
+
+        assert_eq!("missing.level: not found", error.to_string());
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::BTreeMap;
+use std::fmt::{Error, Formatter};
+
+use crate::commands::Command;
+use crate::game_execution::{update_board_from_command, validate_board, ExecutionError};
+use crate::game_model::{Board, Robot};
+use crate::geo::Vector;
+use crate::random::SeededRandom;
+
+/// How to decide which robot gets a cell that more than one robot is trying to move into.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ConflictPolicy {
+    /// The robot that comes first in the fleet goes.
+    Priority,
+    /// A robot picked at random goes, with the same seed always picking the same robots.
+    Random { seed: u64 },
+    /// None of them go.
+    AllBlocked,
+}
+
+/// Why a robot in a fleet did not do what it was told. Robots are numbered by their position in
+/// the fleet.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Conflict {
+    /// The command was rejected, as it would have been with the robot on its own.
+    Rejected { robot: usize, error: ExecutionError },
+    /// Two robots tried to move into each other's cells, so neither went.
+    Swap { robots: (usize, usize) },
+    /// Several robots tried to move into the same cell, and only the winner, if any, went.
+    Contention {
+        location: Vector,
+        robots: Vec<usize>,
+        winner: Option<usize>,
+    },
+    /// The robot tried to move into a cell that another robot stayed in.
+    Blocked { robot: usize, by: usize },
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            Conflict::Rejected { robot, error } => write!(f, "Robot {}: {}", robot, error),
+            Conflict::Swap { robots: (a, b) } => {
+                write!(f, "Robots {} and {} tried to swap places", a, b)
+            }
+            Conflict::Contention {
+                location,
+                robots,
+                winner,
+            } => {
+                let robots: Vec<String> = robots.iter().map(|robot| robot.to_string()).collect();
+
+                write!(
+                    f,
+                    "Robots {} all tried to move to {}; ",
+                    robots.join(","),
+                    location
+                )?;
+
+                match winner {
+                    Some(winner) => write!(f, "robot {} went", winner),
+                    None => write!(f, "none went"),
+                }
+            }
+            Conflict::Blocked { robot, by } => {
+                write!(f, "Robot {} was blocked by robot {}", robot, by)
+            }
+        }
+    }
+}
+
+/// The fleet after a tick, with `None` for robots that were destroyed, and every conflict met
+/// on the way.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Resolution {
+    pub robots: Vec<Option<Robot>>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Runs one command for each robot in `fleet` at the same time, so that no robot sees where
+/// another has just moved to. `commands[i]` is for `fleet[i]`, and a robot without a command
+/// stays put.
+///
+/// Each command is first run as if its robot were alone on `board`. Then robots trying to swap
+/// places are stopped, `policy` settles robots trying to move into the same cell, and robots
+/// moving into a cell that another robot stays in are stopped, repeatedly, since stopping one
+/// robot can leave another's way blocked. A robot that is stopped is left exactly as it was.
+/// Only the robots are updated, so commands that change the board itself have no effect.
+pub fn resolve_simultaneous(
+    board: &Board,
+    fleet: &[Robot],
+    commands: &[Command],
+    policy: ConflictPolicy,
+) -> Resolution {
+    let mut conflicts: Vec<Conflict> = Vec::new();
+
+    let proposed: Vec<Option<Robot>> = fleet
+        .iter()
+        .enumerate()
+        .map(|(index, robot)| match commands.get(index) {
+            Some(command) => {
+                match update_board_from_command(&board.with_robot(robot.clone()), command)
+                    .and_then(|next_board| validate_board(&next_board).map(|_| next_board))
+                {
+                    Ok(next_board) => next_board.robot,
+                    Err(error) => {
+                        conflicts.push(Conflict::Rejected {
+                            robot: index,
+                            error,
+                        });
+                        Some(robot.clone())
+                    }
+                }
+            }
+            None => Some(robot.clone()),
+        })
+        .collect();
+
+    let target = |index: usize| proposed[index].as_ref().map(|robot| robot.location);
+    let is_moving: Vec<bool> = (0..fleet.len())
+        .map(|index| target(index) != Some(fleet[index].location))
+        .collect();
+    let mut goes = is_moving.clone();
+
+    for a in 0..fleet.len() {
+        for b in (a + 1)..fleet.len() {
+            if is_moving[a]
+                && is_moving[b]
+                && target(a) == Some(fleet[b].location)
+                && target(b) == Some(fleet[a].location)
+            {
+                goes[a] = false;
+                goes[b] = false;
+                conflicts.push(Conflict::Swap { robots: (a, b) });
+            }
+        }
+    }
+
+    let mut contenders: BTreeMap<(i16, i16, i16), Vec<usize>> = BTreeMap::new();
+
+    for index in (0..fleet.len()).filter(|index| goes[*index]) {
+        if let Some(location) = target(index) {
+            contenders
+                .entry((location.z, location.y, location.x))
+                .or_default()
+                .push(index);
+        }
+    }
+
+    let mut random = match policy {
+        ConflictPolicy::Random { seed } => Some(SeededRandom::new(seed)),
+        _ => None,
+    };
+
+    for ((z, y, x), robots) in contenders {
+        if robots.len() < 2 {
+            continue;
+        }
+
+        let winner = match (&policy, &mut random) {
+            (ConflictPolicy::Priority, _) => Some(robots[0]),
+            (ConflictPolicy::Random { .. }, Some(random)) => {
+                Some(robots[random.below(robots.len())])
+            }
+            _ => None,
+        };
+
+        for robot in &robots {
+            goes[*robot] = Some(*robot) == winner;
+        }
+
+        conflicts.push(Conflict::Contention {
+            location: Vector::at_level(x, y, z),
+            robots,
+            winner,
+        });
+    }
+
+    let final_location = |goes: &[bool], index: usize| {
+        if goes[index] {
+            target(index)
+        } else {
+            Some(fleet[index].location)
+        }
+    };
+
+    let mut is_settled = false;
+
+    while !is_settled {
+        is_settled = true;
+
+        for robot in 0..fleet.len() {
+            if !goes[robot] {
+                continue;
+            }
+
+            let blocker = (0..fleet.len()).find(|other| {
+                *other != robot
+                    && target(robot).is_some()
+                    && final_location(&goes, *other) == target(robot)
+            });
+
+            if let Some(by) = blocker {
+                goes[robot] = false;
+                is_settled = false;
+                conflicts.push(Conflict::Blocked { robot, by });
+            }
+        }
+    }
+
+    let robots = (0..fleet.len())
+        .map(|index| {
+            if is_moving[index] && !goes[index] {
+                Some(fleet[index].clone())
+            } else {
+                proposed[index].clone()
+            }
+        })
+        .collect();
+
+    Resolution { robots, conflicts }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::Command;
+    use crate::fleet::Conflict::*;
+    use crate::fleet::ConflictPolicy::*;
+    use crate::fleet::{resolve_simultaneous, Resolution};
+    use crate::game_execution::ExecutionError::OutOfBounds;
+    use crate::game_model::{Board, Robot};
+    use crate::geo::Direction::*;
+    use crate::geo::RelativeDirection::Left;
+    use crate::geo::Vector;
+
+    fn empty_board() -> Board {
+        Board::empty_with_corner(&Vector::new(4, 4))
+    }
+
+    /// Two robots either side of (1, 1), both facing it.
+    fn facing_fleet() -> Vec<Robot> {
+        vec![
+            Robot::new(Vector::new(0, 1), East),
+            Robot::new(Vector::new(2, 1), West),
+        ]
+    }
+
+    #[test]
+    fn independent_moves_all_go() {
+        let fleet = vec![
+            Robot::new(Vector::new(0, 0), North),
+            Robot::new(Vector::new(3, 3), South),
+        ];
+
+        assert_eq!(
+            Resolution {
+                robots: vec![
+                    Some(Robot::new(Vector::new(0, 1), North)),
+                    Some(Robot::new(Vector::new(3, 2), South)),
+                ],
+                conflicts: vec![],
+            },
+            resolve_simultaneous(
+                &empty_board(),
+                &fleet,
+                &[Command::Move, Command::Move],
+                Priority
+            )
+        )
+    }
+
+    #[test]
+    fn contention_by_priority() {
+        assert_eq!(
+            Resolution {
+                robots: vec![
+                    Some(Robot::new(Vector::new(1, 1), East)),
+                    Some(Robot::new(Vector::new(2, 1), West)),
+                ],
+                conflicts: vec![Contention {
+                    location: Vector::new(1, 1),
+                    robots: vec![0, 1],
+                    winner: Some(0),
+                }],
+            },
+            resolve_simultaneous(
+                &empty_board(),
+                &facing_fleet(),
+                &[Command::Move, Command::Move],
+                Priority
+            )
+        )
+    }
+
+    #[test]
+    fn contention_all_blocked() {
+        let resolution = resolve_simultaneous(
+            &empty_board(),
+            &facing_fleet(),
+            &[Command::Move, Command::Move],
+            AllBlocked,
+        );
+
+        assert_eq!(
+            vec![
+                Some(Robot::new(Vector::new(0, 1), East)),
+                Some(Robot::new(Vector::new(2, 1), West)),
+            ],
+            resolution.robots
+        )
+    }
+
+    #[test]
+    fn contention_random_is_repeatable() {
+        let resolve = |seed| {
+            resolve_simultaneous(
+                &empty_board(),
+                &facing_fleet(),
+                &[Command::Move, Command::Move],
+                Random { seed },
+            )
+        };
+
+        assert_eq!(resolve(11), resolve(11));
+        assert_eq!(
+            1,
+            resolve(11)
+                .robots
+                .iter()
+                .filter(|robot| robot
+                    .as_ref()
+                    .is_some_and(|robot| robot.location == Vector::new(1, 1)))
+                .count()
+        )
+    }
+
+    #[test]
+    fn swap_blocks_both() {
+        let fleet = vec![
+            Robot::new(Vector::new(0, 1), East),
+            Robot::new(Vector::new(1, 1), West),
+        ];
+
+        assert_eq!(
+            Resolution {
+                robots: fleet.iter().cloned().map(Some).collect(),
+                conflicts: vec![Swap { robots: (0, 1) }],
+            },
+            resolve_simultaneous(
+                &empty_board(),
+                &fleet,
+                &[Command::Move, Command::Move],
+                Priority
+            )
+        )
+    }
+
+    #[test]
+    fn following_a_moving_robot() {
+        let fleet = vec![
+            Robot::new(Vector::new(0, 1), East),
+            Robot::new(Vector::new(1, 1), East),
+        ];
+
+        assert_eq!(
+            vec![
+                Some(Robot::new(Vector::new(1, 1), East)),
+                Some(Robot::new(Vector::new(2, 1), East)),
+            ],
+            resolve_simultaneous(
+                &empty_board(),
+                &fleet,
+                &[Command::Move, Command::Move],
+                Priority
+            )
+            .robots
+        )
+    }
+
+    #[test]
+    fn blocked_by_a_robot_that_stays() {
+        let fleet = vec![
+            Robot::new(Vector::new(0, 1), East),
+            Robot::new(Vector::new(1, 1), East),
+        ];
+
+        assert_eq!(
+            Resolution {
+                robots: vec![
+                    Some(Robot::new(Vector::new(0, 1), East)),
+                    Some(Robot::new(Vector::new(1, 1), North)),
+                ],
+                conflicts: vec![Blocked { robot: 0, by: 1 }],
+            },
+            resolve_simultaneous(
+                &empty_board(),
+                &fleet,
+                &[Command::Move, Command::Rotate(Left)],
+                Priority
+            )
+        )
+    }
+
+    #[test]
+    fn blocked_along_a_chain() {
+        let fleet = vec![
+            Robot::new(Vector::new(2, 0), East),
+            Robot::new(Vector::new(3, 0), East),
+            Robot::new(Vector::new(4, 0), East),
+        ];
+
+        let resolution = resolve_simultaneous(
+            &empty_board(),
+            &fleet,
+            &[Command::Move, Command::Move, Command::Move],
+            Priority,
+        );
+
+        assert_eq!(
+            fleet
+                .iter()
+                .cloned()
+                .map(Some)
+                .collect::<Vec<Option<Robot>>>(),
+            resolution.robots
+        );
+        assert_eq!(
+            vec![
+                Rejected {
+                    robot: 2,
+                    error: OutOfBounds(Vector::new(5, 0))
+                },
+                Blocked { robot: 1, by: 2 },
+                Blocked { robot: 0, by: 1 },
+            ],
+            resolution.conflicts
+        )
+    }
+
+    #[test]
+    fn conflict_report() {
+        assert_eq!(
+            "Robots 0,1 all tried to move to 1,1; robot 0 went",
+            Contention {
+                location: Vector::new(1, 1),
+                robots: vec![0, 1],
+                winner: Some(0),
+            }
+            .to_string()
+        )
+    }
+}
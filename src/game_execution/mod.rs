@@ -1,94 +1,846 @@
+use std::fmt::{Error, Formatter};
+
+use crate::analysis::analysis_report;
 use crate::commands::Command;
-use crate::game_model::{Board, Robot};
+use crate::game_execution::ExecutionError::{
+    Collision, CornerBlocked, InventoryEmpty, NoItemAt, NoObstacleAt, NoRobot, NoSuchLevel,
+    Occupied, OutOfBounds, OutOfEnergy, OutOfRange, OutOfTicks, OverPit, TooManyTeleporters,
+    TooManyTicks, UnsupportedFacing,
+};
+use crate::game_model::{Board, Item, Patrol, Ramp, Robot, Terrain};
+use crate::generation::generate;
+use crate::geo::grid::Grid;
 use crate::geo::{Direction, Vector};
+use crate::planning::coverage::cover;
+use crate::planning::{plan_report, Agent};
+
+pub mod simulator;
+mod stats;
+mod ticks;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ExecutionError {
+    OutOfBounds(Vector),
+    Occupied(Vector),
+    NoObstacleAt(Vector),
+    CornerBlocked(Vector),
+    UnsupportedFacing(Direction),
+    NoSuchLevel(i16),
+    OutOfEnergy {
+        required: u32,
+        remaining: u32,
+    },
+    NoItemAt(Vector),
+    InventoryEmpty,
+    OverPit(Vector),
+    Collision(Vector),
+    /// The next cell from this location would be past the limits of the coordinates.
+    OutOfRange(Vector),
+    TooManyTeleporters,
+    /// The command needs a robot and there is none on the board.
+    NoRobot,
+    TooManyTicks(u32),
+    /// Counting on from this tick would go past the most ticks a board can count.
+    OutOfTicks(u32),
+}
 
-pub fn update_board_from_command(board: &Board, command: &Command) -> Board {
-    return match (board.robot, &command) {
-        (_, Command::Place { location, facing }) => board.with_robot(Robot {
-            location: *location,
-            facing: *facing,
-        }),
-        (None, _) => board.clone(),
-        (Some(robot), Command::Move) => {
-            board.with_robot(robot.with_position(robot.location.translate(robot.facing)))
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            OutOfBounds(location) => write!(f, "Location is out of bounds: {}", location),
+            Occupied(location) => write!(f, "Location is already occupied: {}", location),
+            NoObstacleAt(location) => write!(f, "No obstacle at: {}", location),
+            CornerBlocked(location) => {
+                write!(f, "Cannot cut the corner around obstacle at: {}", location)
+            }
+            UnsupportedFacing(facing) => {
+                write!(f, "Facing not supported on this board: {}", facing)
+            }
+            NoSuchLevel(level) => write!(f, "No such level: {}", level),
+            OutOfEnergy {
+                required,
+                remaining,
+            } => write!(
+                f,
+                "Out of energy: needs {} but has {} remaining",
+                required, remaining
+            ),
+            NoItemAt(location) => write!(f, "No item at: {}", location),
+            InventoryEmpty => write!(f, "Inventory is empty"),
+            OverPit(location) => write!(f, "Location is a pit: {}", location),
+            Collision(location) => write!(f, "Collided with a patrol at: {}", location),
+            OutOfRange(location) => {
+                write!(
+                    f,
+                    "Location is beyond the coordinate limits from: {}",
+                    location
+                )
+            }
+            TooManyTeleporters => write!(
+                f,
+                "No more than {} teleporter pairs fit on a board",
+                MAX_TELEPORTER_PAIRS
+            ),
+            NoRobot => write!(f, "No robot on the board"),
+            TooManyTicks(ticks) => write!(
+                f,
+                "Cannot run more than {} ticks at once: {}",
+                ticks::MAX_TICKS,
+                ticks
+            ),
+            OutOfTicks(tick) => write!(f, "The tick count is at its limit: {}", tick),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+/// The most teleporter pairs a board can have, so that MAP can number every pair with a digit.
+pub const MAX_TELEPORTER_PAIRS: usize = 9;
+
+/// Something that happened to the robot because of the cells it moved over.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum MoveEffect {
+    CrossedCarpet(Vector),
+    SlidOnIce { from: Vector, to: Vector },
+    FellIntoPit(Vector),
+    Teleported { from: Vector, to: Vector },
+    Conveyed { from: Vector, to: Vector },
+}
+
+impl std::fmt::Display for MoveEffect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            MoveEffect::CrossedCarpet(location) => write!(f, "Crossed carpet at: {}", location),
+            MoveEffect::SlidOnIce { from, to } => {
+                write!(f, "Slid on ice from {} to {}", from, to)
+            }
+            MoveEffect::FellIntoPit(location) => {
+                write!(f, "Robot fell into a pit at: {}", location)
+            }
+            MoveEffect::Teleported { from, to } => write!(f, "Teleported from {} to {}", from, to),
+            MoveEffect::Conveyed { from, to } => write!(f, "Conveyed from {} to {}", from, to),
+        }
+    }
+}
+
+/// Runs the command as typed in, returning the board afterwards and what the command printed. A
+/// command that fails, or leaves the board invalid, leaves the board as it was and prints nothing.
+/// Either way the command goes into the board's history for STATS.
+pub fn apply_command(
+    board: &Board,
+    command: &Command,
+) -> (Board, Result<Option<String>, ExecutionError>) {
+    let mut output = Vec::new();
+
+    let outcome = execute(board, command, &mut output)
+        .and_then(|new_board| validate_board(&new_board).map(|_| new_board));
+
+    let recorded_board = stats::record(board, command, &outcome);
+
+    (recorded_board, outcome.map(|_| joined(output)))
+}
+
+pub fn update_board_from_command(
+    board: &Board,
+    command: &Command,
+) -> Result<Board, ExecutionError> {
+    execute(board, command, &mut Vec::new())
+}
+
+/// Each of `commands` that `board` accepts, paired with the valid board it leaves, in the same
+/// order, for searches that try every command from each board they reach.
+pub fn successors(board: &Board, commands: &[Command]) -> Vec<(Command, Board)> {
+    commands
+        .iter()
+        .filter_map(|command| {
+            update_board_from_command(board, command)
+                .and_then(|next_board| validate_board(&next_board).map(|_| next_board))
+                .ok()
+                .map(|next_board| (command.clone(), next_board))
+        })
+        .collect()
+}
+
+/// What the command prints when typed in on `board`, or `None` if it prints nothing or fails.
+pub fn output_from_command(board: &Board, command: &Command) -> Option<String> {
+    apply_command(board, command).1.ok().flatten()
+}
+
+/// Runs the command, adding each line it prints to `output`.
+fn execute(
+    board: &Board,
+    command: &Command,
+    output: &mut Vec<String>,
+) -> Result<Board, ExecutionError> {
+    output.extend(query_output(board, command));
+
+    match (&board.robot, command) {
+        (_, Command::Place { location, facing }) => Ok(board
+            .with_robot(
+                Robot::new(*location, *facing)
+                    .with_energy(board.energy_model.map(|energy_model| energy_model.capacity)),
+            )
+            .with_history(stats::placed(&board.history, *location))),
+        (_, Command::PlaceObjectAt(location)) => place_obstacle(board, *location),
+        (_, Command::RemoveObjectAt(location)) => remove_obstacle(board, *location),
+        (_, Command::ClearObjects) => Ok(board.without_obstacles()),
+        (_, Command::PlaceRamp { location, ramp }) => place_ramp(board, *location, *ramp),
+        (_, Command::PlaceCharger(location)) => place_charger(board, *location),
+        (_, Command::PlaceItem { location, item }) => place_item(board, *location, *item),
+        (_, Command::SetTerrain { location, terrain }) => set_terrain(board, *location, *terrain),
+        (_, Command::PlaceTeleporters(first, second)) => place_teleporters(board, *first, *second),
+        (
+            _,
+            Command::PlaceConveyor {
+                location,
+                direction,
+            },
+        ) => place_conveyor(board, *location, *direction),
+        (_, Command::PlacePatrol(route)) => place_patrol(board, route),
+        (
+            _,
+            Command::Generate {
+                layout,
+                seed,
+                connected,
+            },
+        ) => Ok(generate(board, *layout, *seed, *connected)),
+        (_, Command::Tick(ticks)) => ticks::run_ticks(board, Some(*ticks), output),
+        (_, Command::Run) => ticks::run_ticks(board, None, output),
+        (_, Command::MapLevel(level)) if !board.bounds.contains_level(*level) => {
+            Err(NoSuchLevel(*level))
         }
+        (None, Command::Queue(_)) => Err(NoRobot),
+        (None, _) => Ok(board.clone()),
+        (Some(robot), Command::Move) => move_robot(board, robot, output),
         (Some(robot), Command::Rotate(relative_direction)) => {
-            board.with_robot(robot.with_facing(robot.facing.rotate(relative_direction)))
+            let rotate_cost = board.energy_model.map_or(0, |model| model.rotate_cost);
+
+            spend_energy(robot, rotate_cost).map(|robot| {
+                board
+                    .with_robot(
+                        robot.with_facing(board.grid.rotate(&robot.facing, relative_direction)),
+                    )
+                    .with_history(stats::turned(&board.history))
+            })
         }
         (Some(robot), Command::PlaceObject) => {
-            board.with_obstacle_at(robot.location.translate(robot.facing))
+            cell_ahead(board, robot).and_then(|location| place_obstacle(board, location))
+        }
+        (Some(robot), Command::RemoveObject) => {
+            cell_ahead(board, robot).and_then(|location| remove_obstacle(board, location))
+        }
+        (Some(_robot), Command::Cover) => cover(board, output),
+        (Some(robot), Command::Queue(command)) => Ok(ticks::queue_command(board, robot, command)),
+        (Some(robot), Command::Pick) => pick_item(board, robot),
+        (Some(robot), Command::Drop) => drop_item(board, robot),
+        (Some(_robot), _) => Ok(board.clone()),
+    }
+}
+
+fn move_robot(
+    board: &Board,
+    robot: &Robot,
+    output: &mut Vec<String>,
+) -> Result<Board, ExecutionError> {
+    let trace = trace_move(board, robot)?;
+
+    output.extend(trace.effects.iter().map(MoveEffect::to_string));
+
+    let cost = board.energy_model.map_or(0, |energy_model| {
+        if board.terrain_at(&trace.location) == Terrain::Carpet {
+            energy_model.move_cost + energy_model.carpet_cost
+        } else {
+            energy_model.move_cost
         }
-        (Some(_robot), _) => board.clone(),
+    });
+
+    let robot = spend_energy(robot, cost)?;
+    let board = board.with_history(stats::moved(&board.history, &trace.path));
+
+    Ok(arrive(&board, &robot, trace.location))
+}
+
+/// The board with the robot come to rest at `location`: gone if that is a pit, and with a full
+/// battery if it is a charger.
+fn arrive(board: &Board, robot: &Robot, location: Vector) -> Board {
+    if board.terrain_at(&location) == Terrain::Pit {
+        return board.without_robot();
+    }
+
+    let robot = match board.energy_model {
+        Some(energy_model) if board.charger_locations.contains(&location) => {
+            robot.with_energy(Some(energy_model.capacity))
+        }
+        _ => robot.clone(),
     };
+
+    board.with_robot(robot.with_position(location))
 }
 
-pub fn output_from_command(board: &Board, command: &Command) -> Option<String> {
-    return match (board.robot, &command) {
-        (Some(robot), Command::Report) => Some(
-            format!("{},{},{}", robot.location.x, robot.location.y, robot.facing).to_uppercase(),
-        ),
-        (_, Command::Map) => Some(map_for(board)),
+/// Every cell a MOVE takes the robot into, ending with where it stops, or none if it cannot move.
+pub(crate) fn cells_entered(board: &Board, robot: &Robot) -> Vec<Vector> {
+    trace_move(board, robot).map_or(Vec::new(), |trace| trace.path)
+}
+
+/// Where a MOVE takes the robot and what happened to it on the way.
+struct MoveTrace {
+    location: Vector,
+    effects: Vec<MoveEffect>,
+    /// Each cell entered on the way, ending with `location`.
+    path: Vec<Vector>,
+}
+
+fn trace_move(board: &Board, robot: &Robot) -> Result<MoveTrace, ExecutionError> {
+    // A diagonal move is blocked by an obstacle at either of the two corners it cuts.
+    for corner in board.grid.corners(&robot.location, robot.facing) {
+        if board.obstacle_locations.contains(&corner) {
+            return Err(CornerBlocked(corner));
+        }
+    }
+
+    let mut location =
+        step(board, &robot.location, robot.facing).ok_or(OutOfRange(robot.location))?;
+    let mut effects = Vec::new();
+    let mut path = vec![location];
+
+    if board.terrain_at(&location) == Terrain::Ice {
+        let slide_start = location;
+
+        // Once on ice the robot slides on until something stops it, or it falls into a pit.
+        while board.terrain_at(&location) != Terrain::Pit
+            && !is_blocked(board, &location, robot.facing)
+        {
+            match step(board, &location, robot.facing) {
+                Some(next_location) => location = next_location,
+                None => break,
+            }
+            path.push(location);
+        }
+
+        if location != slide_start {
+            effects.push(MoveEffect::SlidOnIce {
+                from: slide_start,
+                to: location,
+            });
+        }
+    }
+
+    location = teleport(board, location, &mut effects, &mut path);
+
+    if board.terrain_at(&location) == Terrain::Carpet {
+        effects.push(MoveEffect::CrossedCarpet(location));
+    }
+
+    note_pit(board, location, &mut effects);
+
+    Ok(MoveTrace {
+        location,
+        effects,
+        path,
+    })
+}
+
+/// Where a robot arriving at `location` ends up: at the other end of the teleporter there, unless
+/// an obstacle is in the way, or where it arrived otherwise.
+fn teleport(
+    board: &Board,
+    location: Vector,
+    effects: &mut Vec<MoveEffect>,
+    path: &mut Vec<Vector>,
+) -> Vector {
+    match board
+        .teleporter_destination(&location)
+        .filter(|destination| !board.obstacle_locations.contains(destination))
+    {
+        Some(destination) => {
+            effects.push(MoveEffect::Teleported {
+                from: location,
+                to: destination,
+            });
+            path.push(destination);
+            destination
+        }
+        None => location,
+    }
+}
+
+fn note_pit(board: &Board, location: Vector, effects: &mut Vec<MoveEffect>) {
+    if board.terrain_at(&location) == Terrain::Pit {
+        effects.push(MoveEffect::FellIntoPit(location));
+    }
+}
+
+/// The cell one step from `location`, after any ramp there has carried the robot up or down, or
+/// `None` past the limits of the coordinates.
+pub(crate) fn step(board: &Board, location: &Vector, direction: Direction) -> Option<Vector> {
+    let next_location = board.grid.neighbour(location, direction)?;
+
+    match board.ramp_locations.get(&next_location) {
+        Some(ramp) => ramp.destination(&next_location),
+        None => Some(next_location),
+    }
+}
+
+/// The cell in front of the robot.
+fn cell_ahead(board: &Board, robot: &Robot) -> Result<Vector, ExecutionError> {
+    board
+        .grid
+        .neighbour(&robot.location, robot.facing)
+        .ok_or(OutOfRange(robot.location))
+}
+
+fn is_blocked(board: &Board, location: &Vector, direction: Direction) -> bool {
+    let next_location = match board.grid.neighbour(location, direction) {
+        Some(next_location) => next_location,
+        None => return true,
+    };
+
+    !board.contains(&next_location)
+        || board.obstacle_locations.contains(&next_location)
+        || board.has_patrol_at(&next_location)
+        || board
+            .grid
+            .corners(location, direction)
+            .iter()
+            .any(|corner| board.obstacle_locations.contains(corner))
+}
+
+fn spend_energy(robot: &Robot, cost: u32) -> Result<Robot, ExecutionError> {
+    match robot.energy {
+        Some(remaining) if remaining < cost => Err(OutOfEnergy {
+            required: cost,
+            remaining,
+        }),
+        Some(remaining) => Ok(robot.with_energy(Some(remaining - cost))),
+        None => Ok(robot.clone()),
+    }
+}
+
+fn place_obstacle(board: &Board, location: Vector) -> Result<Board, ExecutionError> {
+    if !board.contains(&location) {
+        Err(OutOfBounds(location))
+    } else if is_occupied(board, &location) {
+        Err(Occupied(location))
+    } else {
+        Ok(board.with_obstacle_at(location))
+    }
+}
+
+fn place_ramp(board: &Board, location: Vector, ramp: Ramp) -> Result<Board, ExecutionError> {
+    if !board.contains(&location) {
+        return Err(OutOfBounds(location));
+    }
+
+    let destination = ramp.destination(&location).ok_or(OutOfRange(location))?;
+
+    if !board.contains(&destination) {
+        Err(OutOfBounds(destination))
+    } else if is_occupied(board, &location) {
+        Err(Occupied(location))
+    } else {
+        Ok(board.with_ramp_at(location, ramp))
+    }
+}
+
+fn place_charger(board: &Board, location: Vector) -> Result<Board, ExecutionError> {
+    if !board.contains(&location) {
+        Err(OutOfBounds(location))
+    } else if is_occupied(board, &location) {
+        Err(Occupied(location))
+    } else {
+        Ok(board.with_charger_at(location))
+    }
+}
+
+fn place_teleporters(
+    board: &Board,
+    first: Vector,
+    second: Vector,
+) -> Result<Board, ExecutionError> {
+    if !board.contains(&first) {
+        Err(OutOfBounds(first))
+    } else if !board.contains(&second) {
+        Err(OutOfBounds(second))
+    } else if is_occupied(board, &first) {
+        Err(Occupied(first))
+    } else if first == second || is_occupied(board, &second) {
+        Err(Occupied(second))
+    } else if board.teleporter_pairs.len() >= MAX_TELEPORTER_PAIRS {
+        Err(TooManyTeleporters)
+    } else {
+        Ok(board.with_teleporters_at(first, second))
+    }
+}
+
+fn place_conveyor(
+    board: &Board,
+    location: Vector,
+    direction: Direction,
+) -> Result<Board, ExecutionError> {
+    if !board.contains(&location) {
+        Err(OutOfBounds(location))
+    } else if !board.grid.supports(&direction) {
+        Err(UnsupportedFacing(direction))
+    } else if is_occupied(board, &location) {
+        Err(Occupied(location))
+    } else {
+        Ok(board.with_conveyor_at(location, direction))
+    }
+}
+
+fn place_patrol(board: &Board, route: &[Vector]) -> Result<Board, ExecutionError> {
+    if let Some(location) = route.iter().find(|location| !board.contains(location)) {
+        Err(OutOfBounds(*location))
+    } else if let Some(location) = route
+        .iter()
+        .find(|location| board.obstacle_locations.contains(location))
+    {
+        Err(Occupied(*location))
+    } else {
+        Ok(Patrol::new(route.to_vec()).map_or(board.clone(), |patrol| board.with_patrol(patrol)))
+    }
+}
+
+fn place_item(board: &Board, location: Vector, item: Item) -> Result<Board, ExecutionError> {
+    // Items sit underneath the robot, so unlike other cell contents they may share its cell.
+    if !board.contains(&location) {
+        Err(OutOfBounds(location))
+    } else if board.obstacle_locations.contains(&location)
+        || board.ramp_locations.contains_key(&location)
+        || board.item_locations.contains_key(&location)
+    {
+        Err(Occupied(location))
+    } else {
+        Ok(board.with_item_at(location, item))
+    }
+}
+
+fn pick_item(board: &Board, robot: &Robot) -> Result<Board, ExecutionError> {
+    let item = board
+        .item_locations
+        .get(&robot.location)
+        .ok_or(NoItemAt(robot.location))?;
+
+    let mut inventory = robot.inventory.clone();
+    inventory.push(*item);
+
+    Ok(board
+        .without_item_at(robot.location)
+        .with_robot(robot.with_inventory(inventory)))
+}
+
+fn drop_item(board: &Board, robot: &Robot) -> Result<Board, ExecutionError> {
+    let mut inventory = robot.inventory.clone();
+    let item = inventory.pop().ok_or(InventoryEmpty)?;
+
+    if board.item_locations.contains_key(&robot.location) {
+        return Err(Occupied(robot.location));
+    }
+
+    Ok(board
+        .with_item_at(robot.location, item)
+        .with_robot(robot.with_inventory(inventory)))
+}
+
+fn set_terrain(board: &Board, location: Vector, terrain: Terrain) -> Result<Board, ExecutionError> {
+    if !board.contains(&location) {
+        Err(OutOfBounds(location))
+    } else if terrain == Terrain::Pit
+        && board
+            .robot
+            .as_ref()
+            .is_some_and(|robot| robot.location == location)
+    {
+        Err(Occupied(location))
+    } else {
+        Ok(board.with_terrain_at(location, terrain))
+    }
+}
+
+fn is_occupied(board: &Board, location: &Vector) -> bool {
+    board.obstacle_locations.contains(location)
+        || board.ramp_locations.contains_key(location)
+        || board.charger_locations.contains(location)
+        || board.item_locations.contains_key(location)
+        || board.teleporter_destination(location).is_some()
+        || board.conveyor_locations.contains_key(location)
+        || board.has_patrol_at(location)
+        || board
+            .robot
+            .as_ref()
+            .is_some_and(|robot| robot.location == *location)
+}
+
+fn remove_obstacle(board: &Board, location: Vector) -> Result<Board, ExecutionError> {
+    if !board.contains(&location) {
+        Err(OutOfBounds(location))
+    } else if !board.obstacle_locations.contains(&location) {
+        Err(NoObstacleAt(location))
+    } else {
+        Ok(board.without_obstacle_at(location))
+    }
+}
+
+/// What the command prints about the board as it was before the command ran.
+fn query_output(board: &Board, command: &Command) -> Option<String> {
+    match (&board.robot, command) {
+        (_, Command::PlanAll(starts_and_goals)) => {
+            let agents: Vec<Agent> = starts_and_goals
+                .iter()
+                .map(|(location, facing, goal)| Agent::new(*location, *facing, *goal))
+                .collect();
+
+            Some(plan_report(board, &agents))
+        }
+        (_, Command::Analyze) => Some(analysis_report(board)),
+        (_, Command::Stats) => Some(stats::stats_report(board)),
+        (Some(robot), Command::Report) => Some(report_for(board, robot)),
+        (Some(robot), Command::Inventory) if robot.inventory.is_empty() => {
+            Some("EMPTY".to_string())
+        }
+        (Some(robot), Command::Inventory) => Some(inventory_for(robot)),
+        (Some(robot), Command::Battery) => Some(match (robot.energy, board.energy_model) {
+            (Some(energy), Some(energy_model)) => format!("{}/{}", energy, energy_model.capacity),
+            (Some(energy), None) => format!("{}", energy),
+            (None, _) => "UNLIMITED".to_string(),
+        }),
+        (Some(robot), Command::Map) => Some(map_for(board, robot.location.z)),
+        (None, Command::Map) => Some(map_for(board, board.bounds.bottom_left.z)),
+        (_, Command::MapLevel(level)) if board.bounds.contains_level(*level) => {
+            Some(map_for(board, *level))
+        }
         (_, _) => None,
+    }
+}
+
+/// Lines of output as one, or `None` if there are none.
+fn joined(lines: Vec<String>) -> Option<String> {
+    Some(lines.join("\n")).filter(|_| !lines.is_empty())
+}
+
+fn report_for(board: &Board, robot: &Robot) -> String {
+    let position = if board.is_stacked() {
+        format!(
+            "{},{},{},{}",
+            robot.location.x, robot.location.y, robot.location.z, robot.facing
+        )
+    } else {
+        format!("{},{},{}", robot.location.x, robot.location.y, robot.facing)
     };
+
+    if robot.inventory.is_empty() {
+        position.to_uppercase()
+    } else {
+        format!(
+            "{} CARRYING {}",
+            position.to_uppercase(),
+            inventory_for(robot)
+        )
+    }
+}
+
+fn inventory_for(robot: &Robot) -> String {
+    robot
+        .inventory
+        .iter()
+        .map(|Item(name)| name.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
 }
 
-fn map_for(board: &Board) -> String {
-    let mut s: String = String::new();
+fn map_for(board: &Board, level: i16) -> String {
+    annotated_map_for(board, level, |_| None)
+}
 
-    for y in ((board.bounds.bottom_left.y)..=(board.bounds.top_right.y)).rev() {
-        for x in (board.bounds.bottom_left.x)..=(board.bounds.top_right.y) {
-            let our_vector = Vector::new(x, y);
+/// A MAP of `level` with cells that would otherwise be drawn as empty floor drawn as `annotate`
+/// says instead, where it gives a glyph.
+pub fn annotated_map_for(
+    board: &Board,
+    level: i16,
+    annotate: impl Fn(&Vector) -> Option<char>,
+) -> String {
+    board
+        .grid
+        .layout(&board.bounds)
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|cell| {
+                    cell.map_or(' ', |location| {
+                        let location = location.with_level(level);
+
+                        match glyph_for(board, &location) {
+                            '0' => annotate(&location).unwrap_or('0'),
+                            glyph => glyph,
+                        }
+                    })
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
 
-            if board
-                .robot
-                .map_or_else(|| false, |r| r.location == our_vector)
-            {
-                s.push(match board.robot.unwrap().facing {
-                    Direction::North => '^',
-                    Direction::South => 'v',
-                    Direction::East => '>',
-                    Direction::West => '<',
-                });
-            } else if board.obstacle_locations.contains(&our_vector) {
-                s.push('X');
-            } else {
-                s.push('0');
+fn glyph_for(board: &Board, location: &Vector) -> char {
+    match &board.robot {
+        Some(robot) if robot.location == *location => match robot.facing {
+            Direction::North => '^',
+            Direction::South => 'v',
+            Direction::East => '>',
+            Direction::West => '<',
+            Direction::NorthEast => '↗',
+            Direction::NorthWest => '↖',
+            Direction::SouthEast => '↘',
+            Direction::SouthWest => '↙',
+        },
+        _ if board.has_patrol_at(location) => 'P',
+        _ if board.obstacle_locations.contains(location) => 'X',
+        _ => match (
+            board.item_locations.get(location),
+            board.ramp_locations.get(location),
+        ) {
+            (Some(Item(name)), _) => *name,
+            (None, Some(Ramp::Up)) => 'U',
+            (None, Some(Ramp::Down)) => 'D',
+            (None, None) if board.charger_locations.contains(location) => 'C',
+            (None, None) if board.teleporter_destination(location).is_some() => {
+                teleporter_glyph(board, location)
             }
-        }
-        s.push('\n');
+            (None, None) if board.conveyor_locations.contains_key(location) => {
+                conveyor_glyph(board.conveyor_locations[location])
+            }
+            (None, None) if board.goal_locations.contains(location) => 'G',
+            (None, None) => match board.terrain_at(location) {
+                Terrain::Floor => '0',
+                Terrain::Carpet => '#',
+                Terrain::Ice => '~',
+                Terrain::Pit => '@',
+            },
+        },
     }
+}
 
-    s.pop();
+/// Teleporter pairs are numbered from 1 in the order they were placed, with both ends drawn as
+/// the pair's number. Only a board built by hand can have more than `MAX_TELEPORTER_PAIRS`, and
+/// any pairs beyond that are drawn as `T`.
+fn teleporter_glyph(board: &Board, location: &Vector) -> char {
+    board
+        .teleporter_pairs
+        .iter()
+        .position(|(first, second)| first == location || second == location)
+        .and_then(|index| std::char::from_digit(index as u32 + 1, 10))
+        .unwrap_or('T')
+}
 
-    s
+fn conveyor_glyph(direction: Direction) -> char {
+    match direction {
+        Direction::North => '⇑',
+        Direction::South => '⇓',
+        Direction::East => '⇒',
+        Direction::West => '⇐',
+        Direction::NorthEast => '⇗',
+        Direction::NorthWest => '⇖',
+        Direction::SouthEast => '⇘',
+        Direction::SouthWest => '⇙',
+    }
 }
 
 pub fn is_board_valid(board: &Board) -> bool {
-    board.robot.map_or(true, |robot| {
-        board.bounds.contains(&robot.location)
-            && !board.obstacle_locations.contains(&robot.location)
-    })
+    validate_board(board).is_ok()
+}
+
+pub fn validate_board(board: &Board) -> Result<(), ExecutionError> {
+    if let Some(obstacle_location) = board
+        .obstacle_locations
+        .iter()
+        .find(|location| !board.contains(location))
+    {
+        return Err(OutOfBounds(*obstacle_location));
+    }
+
+    match &board.robot {
+        Some(robot) if !board.contains(&robot.location) => Err(OutOfBounds(robot.location)),
+        Some(robot) if board.obstacle_locations.contains(&robot.location) => {
+            Err(Occupied(robot.location))
+        }
+        Some(robot) if board.has_patrol_at(&robot.location) => Err(Collision(robot.location)),
+        Some(robot) if board.terrain_at(&robot.location) == Terrain::Pit => {
+            Err(OverPit(robot.location))
+        }
+        Some(robot) if !board.grid.supports(&robot.facing) => Err(UnsupportedFacing(robot.facing)),
+        _ => Ok(()),
+    }
 }
 
 #[cfg(test)]
 mod test {
 
-    use crate::game_model::Board;
-    use crate::geo::Vector;
+    use crate::game_model::{Board, EnergyModel};
+    use crate::geo::grid::{GridShape, HexGrid, SquareGrid};
+    use crate::geo::Compass::EightWay;
+    use crate::geo::{Square, Vector};
 
     fn empty_board() -> Board {
         Board::empty_with_corner(&Vector::new(4, 4))
     }
 
+    fn eight_way() -> GridShape {
+        GridShape::Square(SquareGrid { compass: EightWay })
+    }
+
+    fn hex() -> GridShape {
+        GridShape::Hex(HexGrid)
+    }
+
+    fn stacked_board() -> Board {
+        Board::empty_with_corner(&Vector::at_level(4, 4, 2))
+    }
+
+    /// A board in the far north-east corner of the coordinates, where the next cell north or east
+    /// of its edge cannot be represented.
+    fn board_at_the_limits() -> Board {
+        Board {
+            bounds: Square::with_corners(
+                &Vector::new(i16::MAX - 4, i16::MAX - 4),
+                &Vector::new(i16::MAX, i16::MAX),
+            ),
+            ..empty_board()
+        }
+    }
+
+    fn battery_board() -> Board {
+        empty_board().with_energy_model(EnergyModel {
+            capacity: 10,
+            move_cost: 2,
+            rotate_cost: 1,
+            carpet_cost: 3,
+        })
+    }
+
     mod update_board {
         use crate::commands::Command;
-        use crate::game_model::Robot;
+        use crate::game_model::{Board, Item, Patrol, Ramp, Robot, Terrain};
         use crate::geo::Direction::*;
         use crate::geo::RelativeDirection::Left;
         use crate::geo::Vector;
 
-        use super::super::update_board_from_command;
-        use super::empty_board;
+        use super::super::ExecutionError::*;
+        use super::super::{
+            update_board_from_command, validate_board, ExecutionError, MAX_TELEPORTER_PAIRS,
+        };
+        use super::{
+            battery_board, board_at_the_limits, eight_way, empty_board, hex, stacked_board,
+        };
+
+        /// The board the command leaves, apart from the history kept for STATS, which the stats
+        /// tests cover.
+        fn updated(board: &Board, command: &Command) -> Result<Board, ExecutionError> {
+            update_board_from_command(board, command).map(|board| board.without_history())
+        }
 
         #[test]
         fn update_board_move_no_robot() {
@@ -97,9 +849,88 @@ mod test {
             let initial_board = empty_board();
             let expected_board = empty_board();
 
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_past_the_coordinate_limits() {
+            let command = Command::Move;
+
+            let initial_board = board_at_the_limits()
+                .with_robot(Robot::new(Vector::new(i16::MAX - 2, i16::MAX), North));
+
+            assert_eq!(
+                Err(OutOfRange(Vector::new(i16::MAX - 2, i16::MAX))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_move_diagonally_past_the_coordinate_limits() {
+            let command = Command::Move;
+
+            let initial_board = board_at_the_limits()
+                .with_grid(eight_way())
+                .with_robot(Robot::new(Vector::new(i16::MAX, i16::MAX - 2), NorthEast));
+
+            assert_eq!(
+                Err(OutOfRange(Vector::new(i16::MAX, i16::MAX - 2))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_move_up_to_the_coordinate_limits() {
+            let command = Command::Move;
+
+            let initial_board = board_at_the_limits()
+                .with_robot(Robot::new(Vector::new(i16::MAX - 1, i16::MAX), East));
+            let expected_board =
+                board_at_the_limits().with_robot(Robot::new(Vector::new(i16::MAX, i16::MAX), East));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_slide_stops_at_the_coordinate_limits() {
+            let command = Command::Move;
+
+            let initial_board = board_at_the_limits()
+                .with_terrain_at(Vector::new(i16::MAX - 1, i16::MAX), Terrain::Ice)
+                .with_terrain_at(Vector::new(i16::MAX, i16::MAX), Terrain::Ice)
+                .with_robot(Robot::new(Vector::new(i16::MAX - 2, i16::MAX), East));
+
+            assert_eq!(
+                Ok(initial_board.with_robot(Robot::new(Vector::new(i16::MAX, i16::MAX), East))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_place_object_past_the_coordinate_limits() {
+            let command = Command::PlaceObject;
+
+            let initial_board =
+                board_at_the_limits().with_robot(Robot::new(Vector::new(i16::MAX, i16::MAX), East));
+
+            assert_eq!(
+                Err(OutOfRange(Vector::new(i16::MAX, i16::MAX))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_place_out_at_the_coordinate_limits() {
+            let command = Command::Place {
+                location: Vector::new(i16::MAX, 0),
+                facing: North,
+            };
+
+            let placed_board = updated(&empty_board(), &command).unwrap();
+
             assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
+                Err(OutOfBounds(Vector::new(i16::MAX, 0))),
+                validate_board(&placed_board)
             )
         }
 
@@ -110,197 +941,1228 @@ mod test {
             let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
             let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 2), North));
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_with_robot_on_edge() {
+            let command = Command::Move;
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(0, 0), West));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(-1, 0), West));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_rotate_no_robot() {
+            let command = Command::Rotate(Left);
+
+            let initial_board = empty_board();
+            let expected_board = empty_board();
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_rotate_with_robot() {
+            let command = Command::Rotate(Left);
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 1), West));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_rotate_eight_way() {
+            let command = Command::Rotate(Left);
+
+            let initial_board = empty_board()
+                .with_grid(eight_way())
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 1), NorthWest));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_diagonally() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_grid(eight_way())
+                .with_robot(Robot::new(Vector::new(1, 1), NorthEast));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(2, 2), NorthEast));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_diagonally_past_obstacle() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_grid(eight_way())
+                .with_robot(Robot::new(Vector::new(1, 1), SouthWest))
+                .with_obstacle_at(Vector::new(0, 1));
+
+            assert_eq!(
+                Err(CornerBlocked(Vector::new(0, 1))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_rotate_hex() {
+            let command = Command::Rotate(Left);
+
+            let initial_board = empty_board()
+                .with_grid(hex())
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 1), NorthWest));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_hex() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_grid(hex())
+                .with_robot(Robot::new(Vector::new(1, 1), SouthEast));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(2, 1), SouthEast));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_hex_east_or_west() {
+            for facing in &[East, West] {
+                let initial_board = empty_board()
+                    .with_grid(hex())
+                    .with_terrain_at(Vector::new(1, 1), Terrain::Ice)
+                    .with_robot(Robot::new(Vector::new(1, 1), *facing));
+
+                assert_eq!(
+                    Err(OutOfRange(Vector::new(1, 1))),
+                    updated(&initial_board, &Command::Move)
+                )
+            }
+        }
+
+        #[test]
+        fn update_board_move_onto_ramp_up() {
+            let command = Command::Move;
+
+            let initial_board = stacked_board()
+                .with_ramp_at(Vector::new(1, 2), Ramp::Up)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board =
+                initial_board.with_robot(Robot::new(Vector::at_level(1, 2, 1), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_onto_ramp_down() {
+            let command = Command::Move;
+
+            let initial_board = stacked_board()
+                .with_ramp_at(Vector::at_level(2, 1, 2), Ramp::Down)
+                .with_robot(Robot::new(Vector::at_level(1, 1, 2), East));
+            let expected_board =
+                initial_board.with_robot(Robot::new(Vector::at_level(2, 1, 1), East));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_ramp() {
+            let command = Command::PlaceRamp {
+                location: Vector::at_level(2, 2, 1),
+                ramp: Ramp::Up,
+            };
+
+            let initial_board = stacked_board();
+            let expected_board = stacked_board().with_ramp_at(Vector::at_level(2, 2, 1), Ramp::Up);
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_ramp_off_top_level() {
+            let command = Command::PlaceRamp {
+                location: Vector::at_level(2, 2, 2),
+                ramp: Ramp::Up,
+            };
+
+            let initial_board = stacked_board();
+
+            assert_eq!(
+                Err(OutOfBounds(Vector::at_level(2, 2, 3))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        // Found by fuzzing: the level above was worked out before checking the ramp was on the
+        // board, and overflowed.
+        #[test]
+        fn update_board_place_ramp_on_the_highest_level_there_could_be() {
+            let command = Command::PlaceRamp {
+                location: Vector::at_level(4, 1, i16::MAX),
+                ramp: Ramp::Up,
+            };
+
+            let initial_board = stacked_board();
+
+            assert_eq!(
+                Err(OutOfBounds(Vector::at_level(4, 1, i16::MAX))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_place_object_on_ramp() {
+            let command = Command::PlaceObjectAt(Vector::new(2, 2));
+
+            let initial_board = stacked_board().with_ramp_at(Vector::new(2, 2), Ramp::Up);
+
+            assert_eq!(
+                Err(Occupied(Vector::new(2, 2))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_map_missing_level() {
+            let command = Command::MapLevel(3);
+
+            let initial_board = stacked_board();
+
+            assert_eq!(Err(NoSuchLevel(3)), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_with_battery() {
+            let command = Command::Place {
+                location: Vector::new(1, 1),
+                facing: North,
+            };
+
+            let initial_board = battery_board();
+            let expected_board = initial_board
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_energy(Some(10)));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_spends_energy() {
+            let command = Command::Move;
+
+            let initial_board = battery_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_energy(Some(5)));
+            let expected_board =
+                initial_board.with_robot(Robot::new(Vector::new(1, 2), North).with_energy(Some(3)));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_rotate_spends_energy() {
+            let command = Command::Rotate(Left);
+
+            let initial_board = battery_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_energy(Some(5)));
+            let expected_board =
+                initial_board.with_robot(Robot::new(Vector::new(1, 1), West).with_energy(Some(4)));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_out_of_energy() {
+            let command = Command::Move;
+
+            let initial_board = battery_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_energy(Some(1)));
+
+            assert_eq!(
+                Err(OutOfEnergy {
+                    required: 2,
+                    remaining: 1
+                }),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_move_onto_charger() {
+            let command = Command::Move;
+
+            let initial_board = battery_board()
+                .with_charger_at(Vector::new(1, 2))
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_energy(Some(2)));
+            let expected_board = initial_board
+                .with_robot(Robot::new(Vector::new(1, 2), North).with_energy(Some(10)));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_charger_on_obstacle() {
+            let command = Command::PlaceCharger(Vector::new(2, 2));
+
+            let initial_board = battery_board().with_obstacle_at(Vector::new(2, 2));
+
+            assert_eq!(
+                Err(Occupied(Vector::new(2, 2))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_place_item() {
+            let command = Command::PlaceItem {
+                location: Vector::new(1, 1),
+                item: Item('a'),
+            };
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_item_at(Vector::new(1, 1), Item('a'));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_item_on_obstacle() {
+            let command = Command::PlaceItem {
+                location: Vector::new(1, 1),
+                item: Item('a'),
+            };
+
+            let initial_board = empty_board().with_obstacle_at(Vector::new(1, 1));
+
+            assert_eq!(
+                Err(Occupied(Vector::new(1, 1))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_pick() {
+            let command = Command::Pick;
+
+            let initial_board = empty_board()
+                .with_item_at(Vector::new(1, 1), Item('a'))
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_inventory(vec![Item('b')]));
+            let expected_board = empty_board().with_robot(
+                Robot::new(Vector::new(1, 1), North).with_inventory(vec![Item('b'), Item('a')]),
+            );
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_pick_nothing() {
+            let command = Command::Pick;
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(
+                Err(NoItemAt(Vector::new(1, 1))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_drop() {
+            let command = Command::Drop;
+
+            let initial_board = empty_board().with_robot(
+                Robot::new(Vector::new(1, 1), North).with_inventory(vec![Item('b'), Item('a')]),
+            );
+            let expected_board = empty_board()
+                .with_item_at(Vector::new(1, 1), Item('a'))
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_inventory(vec![Item('b')]));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_drop_empty_inventory() {
+            let command = Command::Drop;
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(Err(InventoryEmpty), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_drop_onto_item() {
+            let command = Command::Drop;
+
+            let initial_board = empty_board()
+                .with_item_at(Vector::new(1, 1), Item('c'))
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_inventory(vec![Item('a')]));
+
+            assert_eq!(
+                Err(Occupied(Vector::new(1, 1))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_place_no_robot() {
+            let command = Command::Place {
+                location: Vector::new(1, 1),
+                facing: North,
+            };
+
+            let initial_board = empty_board();
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_robot_invalid() {
+            let command = Command::Place {
+                location: Vector::new(-1, -1),
+                facing: North,
+            };
+
+            let initial_board = empty_board();
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(-1, -1), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_with_robot() {
+            let command = Command::Place {
+                location: Vector::new(1, 1),
+                facing: North,
+            };
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(4, 4), South));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_object_no_robot() {
+            let command = Command::PlaceObject;
+
+            let initial_board = empty_board();
+            let expected_board = empty_board();
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_object_with_robot() {
+            let command = Command::PlaceObject;
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_obstacle_at(Vector::new(1, 2));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_object_with_robot_and_obstacle() {
+            let command = Command::PlaceObject;
+
+            let initial_board = empty_board()
+                .with_robot(Robot::new(Vector::new(3, 3), South))
+                .with_obstacle_at(Vector::new(1, 2));
+
+            let expected_board = initial_board.with_obstacle_at(Vector::new(3, 2));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_object_with_robot_facing_edge() {
+            let command = Command::PlaceObject;
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(4, 2), East));
+
+            assert_eq!(
+                Err(OutOfBounds(Vector::new(5, 2))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_place_object_with_robot_facing_obstacle() {
+            let command = Command::PlaceObject;
+
+            let initial_board = empty_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North))
+                .with_obstacle_at(Vector::new(1, 2));
+
+            assert_eq!(
+                Err(Occupied(Vector::new(1, 2))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_place_object_at_robot() {
+            let command = Command::PlaceObjectAt(Vector::new(1, 1));
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(
+                Err(Occupied(Vector::new(1, 1))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_remove_object_with_robot() {
+            let command = Command::RemoveObject;
+
+            let initial_board = empty_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North))
+                .with_obstacle_at(Vector::new(1, 2));
+            let expected_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_remove_object_with_robot_no_obstacle() {
+            let command = Command::RemoveObject;
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(
+                Err(NoObstacleAt(Vector::new(1, 2))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_remove_object_with_robot_facing_edge() {
+            let command = Command::RemoveObject;
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(0, 0), West));
+
+            assert_eq!(
+                Err(OutOfBounds(Vector::new(-1, 0))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_place_object_at_no_robot() {
+            let command = Command::PlaceObjectAt(Vector::new(2, 3));
+
+            let initial_board = empty_board();
+            let expected_board = empty_board().with_obstacle_at(Vector::new(2, 3));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_object_at_out_of_bounds() {
+            let command = Command::PlaceObjectAt(Vector::new(5, 3));
+
+            let initial_board = empty_board();
+
+            assert_eq!(
+                Err(OutOfBounds(Vector::new(5, 3))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_remove_object_at() {
+            let command = Command::RemoveObjectAt(Vector::new(2, 3));
+
+            let initial_board = empty_board()
+                .with_obstacle_at(Vector::new(2, 3))
+                .with_obstacle_at(Vector::new(1, 1));
+            let expected_board = empty_board().with_obstacle_at(Vector::new(1, 1));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_remove_object_at_no_obstacle() {
+            let command = Command::RemoveObjectAt(Vector::new(2, 3));
+
+            let initial_board = empty_board();
+
+            assert_eq!(
+                Err(NoObstacleAt(Vector::new(2, 3))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_remove_object_at_out_of_bounds() {
+            let command = Command::RemoveObjectAt(Vector::new(-1, 3));
+
+            let initial_board = empty_board();
+
+            assert_eq!(
+                Err(OutOfBounds(Vector::new(-1, 3))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_clear_objects() {
+            let command = Command::ClearObjects;
+
+            let initial_board = empty_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North))
+                .with_obstacle_at(Vector::new(2, 3))
+                .with_obstacle_at(Vector::new(1, 2));
+            let expected_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_set_terrain() {
+            let command = Command::SetTerrain {
+                location: Vector::new(2, 3),
+                terrain: Terrain::Ice,
+            };
+
+            let initial_board = empty_board();
+            let expected_board = empty_board().with_terrain_at(Vector::new(2, 3), Terrain::Ice);
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_set_terrain_out_of_bounds() {
+            let command = Command::SetTerrain {
+                location: Vector::new(5, 3),
+                terrain: Terrain::Carpet,
+            };
+
+            let initial_board = empty_board();
+
+            assert_eq!(
+                Err(OutOfBounds(Vector::new(5, 3))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_set_pit_under_robot() {
+            let command = Command::SetTerrain {
+                location: Vector::new(1, 1),
+                terrain: Terrain::Pit,
+            };
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(
+                Err(Occupied(Vector::new(1, 1))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_move_onto_carpet_costs_extra() {
+            let command = Command::Move;
+
+            let initial_board = battery_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Carpet)
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_energy(Some(10)));
+            let expected_board = battery_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Carpet)
+                .with_robot(Robot::new(Vector::new(1, 2), North).with_energy(Some(5)));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_slides_across_ice() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Ice)
+                .with_terrain_at(Vector::new(1, 3), Terrain::Ice)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 4), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_slide_stops_at_obstacle() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Ice)
+                .with_terrain_at(Vector::new(1, 3), Terrain::Ice)
+                .with_obstacle_at(Vector::new(1, 4))
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 3), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_slide_stops_at_edge() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_terrain_at(Vector::new(1, 3), Terrain::Ice)
+                .with_terrain_at(Vector::new(1, 4), Terrain::Ice)
+                .with_robot(Robot::new(Vector::new(1, 2), North));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 4), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_slides_on_past_the_ice() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Ice)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 4), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_slides_into_pit() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Ice)
+                .with_terrain_at(Vector::new(1, 3), Terrain::Pit)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.without_robot();
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_into_pit() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Pit)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = empty_board().with_terrain_at(Vector::new(1, 2), Terrain::Pit);
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_teleporters() {
+            let command = Command::PlaceTeleporters(Vector::new(0, 1), Vector::new(3, 4));
+
+            let initial_board = empty_board();
+            let expected_board =
+                empty_board().with_teleporters_at(Vector::new(0, 1), Vector::new(3, 4));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_teleporters_on_same_cell() {
+            let command = Command::PlaceTeleporters(Vector::new(0, 1), Vector::new(0, 1));
+
+            let initial_board = empty_board();
+
+            assert_eq!(
+                Err(Occupied(Vector::new(0, 1))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_place_teleporters_past_the_last_number() {
+            let command = Command::PlaceTeleporters(Vector::new(4, 0), Vector::new(4, 1));
+
+            let initial_board = (0..MAX_TELEPORTER_PAIRS as i16).fold(empty_board(), |board, x| {
+                board.with_teleporters_at(
+                    Vector::new(x % 4, x / 4 * 2),
+                    Vector::new(x % 4, x / 4 * 2 + 1),
+                )
+            });
+
+            assert_eq!(Err(TooManyTeleporters), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_conveyor_on_teleporter() {
+            let command = Command::PlaceConveyor {
+                location: Vector::new(3, 4),
+                direction: South,
+            };
+
+            let initial_board =
+                empty_board().with_teleporters_at(Vector::new(0, 1), Vector::new(3, 4));
+
+            assert_eq!(
+                Err(Occupied(Vector::new(3, 4))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_move_onto_teleporter() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_teleporters_at(Vector::new(1, 2), Vector::new(3, 4))
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(3, 4), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_move_onto_conveyor() {
+            let command = Command::Move;
+
+            let initial_board = empty_board()
+                .with_conveyor_at(Vector::new(1, 2), East)
+                .with_conveyor_at(Vector::new(2, 2), East)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 2), North));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_patrol() {
+            let command = Command::PlacePatrol(vec![Vector::new(0, 1), Vector::new(0, 2)]);
+
+            let initial_board = empty_board();
+            let expected_board = empty_board()
+                .with_patrol(Patrol::new(vec![Vector::new(0, 1), Vector::new(0, 2)]).unwrap());
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_patrol_through_obstacle() {
+            let command = Command::PlacePatrol(vec![Vector::new(0, 1), Vector::new(0, 2)]);
+
+            let initial_board = empty_board().with_obstacle_at(Vector::new(0, 2));
+
+            assert_eq!(
+                Err(Occupied(Vector::new(0, 2))),
+                updated(&initial_board, &command)
+            )
+        }
+
+        #[test]
+        fn update_board_queue() {
+            let command = Command::Queue(Box::new(Command::Move));
+
+            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_board = empty_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_queue(vec![Command::Move]));
+
+            assert_eq!(Ok(expected_board), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_queue_no_robot() {
+            let command = Command::Queue(Box::new(Command::Move));
+
+            let initial_board = empty_board();
+
+            assert_eq!(Err(NoRobot), updated(&initial_board, &command))
+        }
+
+        #[test]
+        fn update_board_place_patrol_with_empty_route() {
+            let command = Command::PlacePatrol(vec![]);
+
+            let initial_board = empty_board();
+
+            assert_eq!(Ok(empty_board()), updated(&initial_board, &command))
+        }
+    }
+
+    mod output {
+        use crate::commands::Command;
+        use crate::game_model::{Board, Item, Patrol, Ramp, Robot, Terrain};
+        use crate::geo::Direction::*;
+
+        use crate::geo::Vector;
+
+        use super::super::output_from_command;
+        use super::{battery_board, eight_way, empty_board, hex, stacked_board};
+
+        #[test]
+        fn output_move_with_robot() {
+            let command = Command::Move;
+
+            let board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_output = None;
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_report_with_no_robot() {
+            let command = Command::Report;
+
+            let board = empty_board();
+            let expected_output = None;
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_report_with_robot() {
+            let command = Command::Report;
+
+            let board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_output = Some("1,1,NORTH".to_string());
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_map() {
+            let command = Command::Map;
+
+            let board = empty_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North))
+                .with_obstacle_at(Vector::new(4, 4))
+                .with_obstacle_at(Vector::new(1, 2));
+            let expected_output = Some(
+                "0000X\n\
+                 00000\n\
+                 0X000\n\
+                 0^000\n\
+                 00000"
+                    .to_string(),
+            );
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_map_diagonal_facing() {
+            let command = Command::Map;
+
+            let board = Board::empty_with_corner(&Vector::new(2, 2))
+                .with_grid(eight_way())
+                .with_robot(Robot::new(Vector::new(1, 1), SouthEast));
+            let expected_output = Some(
+                "000\n\
+                 0↘0\n\
+                 000"
+                .to_string(),
+            );
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_map_hex() {
+            let command = Command::Map;
+
+            let board = Board::empty_with_corner(&Vector::new(2, 1))
+                .with_grid(hex())
+                .with_robot(Robot::new(Vector::new(1, 1), North))
+                .with_obstacle_at(Vector::new(2, 1));
+            let expected_output = Some(
+                "0 0\n\
+                 \x20^\n\
+                 0 X\n\
+                 \x200"
+                    .to_string(),
+            );
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_report_with_robot_on_stacked_board() {
+            let command = Command::Report;
+
+            let board = stacked_board().with_robot(Robot::new(Vector::at_level(1, 1, 2), North));
+            let expected_output = Some("1,1,2,NORTH".to_string());
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_map_level() {
+            let command = Command::MapLevel(1);
+
+            let board = Board::empty_with_corner(&Vector::at_level(2, 2, 1))
+                .with_robot(Robot::new(Vector::new(0, 0), North))
+                .with_obstacle_at(Vector::at_level(2, 2, 1))
+                .with_ramp_at(Vector::at_level(1, 1, 1), Ramp::Down);
+            let expected_output = Some(
+                "00X\n\
+                 0D0\n\
+                 000"
+                .to_string(),
+            );
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_map_shows_robot_level() {
+            let command = Command::Map;
+
+            let board = Board::empty_with_corner(&Vector::at_level(2, 2, 1))
+                .with_robot(Robot::new(Vector::at_level(0, 0, 1), North))
+                .with_obstacle_at(Vector::new(2, 2));
+            let expected_output = Some(
+                "000\n\
+                 000\n\
+                 ^00"
+                .to_string(),
+            );
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_battery() {
+            let command = Command::Battery;
+
+            let board = battery_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_energy(Some(7)));
+            let expected_output = Some("7/10".to_string());
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_battery_unlimited() {
+            let command = Command::Battery;
+
+            let board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_output = Some("UNLIMITED".to_string());
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn update_board_move_with_robot_on_edge() {
-            let command = Command::Move;
+        fn output_map_charger() {
+            let command = Command::Map;
 
-            let initial_board = empty_board().with_robot(Robot::new(Vector::new(0, 0), West));
-            let expected_board = initial_board.with_robot(Robot::new(Vector::new(-1, 0), West));
+            let board = Board::empty_with_corner(&Vector::new(1, 1))
+                .with_charger_at(Vector::new(1, 0))
+                .with_robot(Robot::new(Vector::new(0, 0), East));
+            let expected_output = Some(
+                "00\n\
+                 >C"
+                .to_string(),
+            );
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn update_board_rotate_no_robot() {
-            let command = Command::Rotate(Left);
+        fn output_report_carrying_items() {
+            let command = Command::Report;
 
-            let initial_board = empty_board();
-            let expected_board = empty_board();
+            let board = empty_board().with_robot(
+                Robot::new(Vector::new(1, 1), North).with_inventory(vec![Item('a'), Item('b')]),
+            );
+            let expected_output = Some("1,1,NORTH CARRYING a,b".to_string());
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn update_board_rotate_with_robot() {
-            let command = Command::Rotate(Left);
+        fn output_inventory() {
+            let command = Command::Inventory;
 
-            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
-            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 1), West));
+            let board = empty_board().with_robot(
+                Robot::new(Vector::new(1, 1), North).with_inventory(vec![Item('a'), Item('b')]),
+            );
+            let expected_output = Some("a,b".to_string());
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn update_board_place_no_robot() {
-            let command = Command::Place {
-                location: Vector::new(1, 1),
-                facing: North,
-            };
+        fn output_inventory_empty() {
+            let command = Command::Inventory;
 
-            let initial_board = empty_board();
-            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 1), North));
+            let board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_output = Some("EMPTY".to_string());
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn update_board_place_robot_invalid() {
-            let command = Command::Place {
-                location: Vector::new(-1, -1),
-                facing: North,
-            };
+        fn output_map_items() {
+            let command = Command::Map;
 
-            let initial_board = empty_board();
-            let expected_board = initial_board.with_robot(Robot::new(Vector::new(-1, -1), North));
+            let board = Board::empty_with_corner(&Vector::new(1, 1))
+                .with_item_at(Vector::new(1, 1), Item('k'))
+                .with_item_at(Vector::new(0, 0), Item('j'))
+                .with_robot(Robot::new(Vector::new(0, 0), East));
+            let expected_output = Some(
+                "0k\n\
+                 >0"
+                .to_string(),
+            );
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn update_board_place_with_robot() {
-            let command = Command::Place {
-                location: Vector::new(1, 1),
-                facing: North,
-            };
+        fn output_map_goal() {
+            let command = Command::Map;
 
-            let initial_board = empty_board().with_robot(Robot::new(Vector::new(4, 4), South));
-            let expected_board = initial_board.with_robot(Robot::new(Vector::new(1, 1), North));
+            let board = Board::empty_with_corner(&Vector::new(1, 1))
+                .with_goal_at(Vector::new(1, 1))
+                .with_robot(Robot::new(Vector::new(0, 0), North));
+            let expected_output = Some(
+                "0G\n\
+                 ^0"
+                .to_string(),
+            );
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn update_board_place_object_no_robot() {
-            let command = Command::PlaceObject;
+        fn output_report_diagonal_facing() {
+            let command = Command::Report;
 
-            let initial_board = empty_board();
-            let expected_board = empty_board();
+            let board = empty_board()
+                .with_grid(eight_way())
+                .with_robot(Robot::new(Vector::new(1, 1), NorthWest));
+            let expected_output = Some("1,1,NORTHWEST".to_string());
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn update_board_place_object_with_robot() {
-            let command = Command::PlaceObject;
+        fn output_move_onto_carpet() {
+            let command = Command::Move;
 
-            let initial_board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
-            let expected_board = initial_board.with_obstacle_at(Vector::new(1, 2));
+            let board = empty_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Carpet)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_output = Some("Crossed carpet at: 1,2".to_string());
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn update_board_place_object_with_robot_and_obstacle() {
-            let command = Command::PlaceObject;
-
-            let initial_board = empty_board()
-                .with_robot(Robot::new(Vector::new(3, 3), South))
-                .with_obstacle_at(Vector::new(1, 2));
+        fn output_move_slides_on_ice() {
+            let command = Command::Move;
 
-            let expected_board = initial_board.with_obstacle_at(Vector::new(3, 2));
+            let board = empty_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Ice)
+                .with_terrain_at(Vector::new(1, 3), Terrain::Ice)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_output = Some("Slid on ice from 1,2 to 1,4".to_string());
 
-            assert_eq!(
-                expected_board,
-                update_board_from_command(&initial_board, &command)
-            )
+            assert_eq!(expected_output, output_from_command(&board, &command))
         }
-    }
 
-    mod output {
-        use crate::commands::Command;
-        use crate::game_model::Robot;
-        use crate::geo::Direction::*;
+        #[test]
+        fn output_move_into_pit() {
+            let command = Command::Move;
 
-        use crate::geo::Vector;
+            let board = empty_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Pit)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_output = Some("Robot fell into a pit at: 1,2".to_string());
 
-        use super::super::output_from_command;
-        use super::empty_board;
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
 
         #[test]
-        fn output_move_with_robot() {
+        fn output_move_onto_carpet_without_energy() {
             let command = Command::Move;
 
-            let board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
+            let board = battery_board()
+                .with_terrain_at(Vector::new(1, 2), Terrain::Carpet)
+                .with_robot(Robot::new(Vector::new(1, 1), North).with_energy(Some(4)));
             let expected_output = None;
 
             assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn output_report_with_no_robot() {
-            let command = Command::Report;
+        fn output_map_terrain() {
+            let command = Command::Map;
 
-            let board = empty_board();
-            let expected_output = None;
+            let board = Board::empty_with_corner(&Vector::new(2, 1))
+                .with_terrain_at(Vector::new(0, 1), Terrain::Carpet)
+                .with_terrain_at(Vector::new(1, 1), Terrain::Ice)
+                .with_terrain_at(Vector::new(2, 1), Terrain::Pit)
+                .with_terrain_at(Vector::new(0, 0), Terrain::Ice)
+                .with_robot(Robot::new(Vector::new(0, 0), North));
+            let expected_output = Some(
+                "#~@\n\
+                 ^00"
+                .to_string(),
+            );
 
             assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn output_report_with_robot() {
-            let command = Command::Report;
+        fn output_move_through_teleporter_onto_conveyor() {
+            let command = Command::Move;
 
-            let board = empty_board().with_robot(Robot::new(Vector::new(1, 1), North));
-            let expected_output = Some("1,1,NORTH".to_string());
+            let board = empty_board()
+                .with_teleporters_at(Vector::new(1, 2), Vector::new(3, 3))
+                .with_conveyor_at(Vector::new(3, 3), North)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+            let expected_output = Some("Teleported from 1,2 to 3,3".to_string());
 
             assert_eq!(expected_output, output_from_command(&board, &command))
         }
 
         #[test]
-        fn output_map() {
+        fn output_map_teleporters_and_conveyors() {
             let command = Command::Map;
 
-            let board = empty_board()
-                .with_robot(Robot::new(Vector::new(1, 1), North))
-                .with_obstacle_at(Vector::new(4, 4))
-                .with_obstacle_at(Vector::new(1, 2));
+            let board = Board::empty_with_corner(&Vector::new(2, 1))
+                .with_teleporters_at(Vector::new(0, 1), Vector::new(2, 0))
+                .with_conveyor_at(Vector::new(1, 1), East)
+                .with_conveyor_at(Vector::new(1, 0), South)
+                .with_robot(Robot::new(Vector::new(0, 0), North));
             let expected_output = Some(
-                "0000X\n\
-                 00000\n\
-                 0X000\n\
-                 0^000\n\
-                 00000"
-                    .to_string(),
+                "1⇒0\n\
+                 ^⇓1"
+                .to_string(),
+            );
+
+            assert_eq!(expected_output, output_from_command(&board, &command))
+        }
+
+        #[test]
+        fn output_map_patrol() {
+            let command = Command::Map;
+
+            let board = Board::empty_with_corner(&Vector::new(1, 1))
+                .with_patrol(Patrol::new(vec![Vector::new(1, 1), Vector::new(1, 0)]).unwrap())
+                .with_robot(Robot::new(Vector::new(0, 0), North));
+            let expected_output = Some(
+                "0P\n\
+                 ^0"
+                .to_string(),
             );
 
             assert_eq!(expected_output, output_from_command(&board, &command))
@@ -309,13 +2171,14 @@ mod test {
 
     mod validate {
 
-        use crate::game_model::Robot;
+        use crate::game_model::{Patrol, Robot, Terrain};
         use crate::geo::Direction::*;
 
         use crate::geo::Vector;
 
-        use super::super::is_board_valid;
-        use super::empty_board;
+        use super::super::ExecutionError::*;
+        use super::super::{is_board_valid, validate_board};
+        use super::{eight_way, empty_board, hex};
 
         #[test]
         fn validate_no_robot() {
@@ -374,5 +2237,334 @@ mod test {
 
             assert_eq!(expected_valid, is_board_valid(&board),)
         }
+
+        #[test]
+        fn validate_with_obstacle_out_of_bounds() {
+            let board = empty_board().with_obstacle_at(Vector::new(5, 5));
+            let expected_valid = false;
+
+            assert_eq!(expected_valid, is_board_valid(&board),)
+        }
+
+        #[test]
+        fn validate_reason_robot_out_of_bounds() {
+            let board = empty_board().with_robot(Robot::new(Vector::new(-1, 1), North));
+
+            assert_eq!(Err(OutOfBounds(Vector::new(-1, 1))), validate_board(&board))
+        }
+
+        #[test]
+        fn validate_reason_robot_on_obstacle() {
+            let board = empty_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North))
+                .with_obstacle_at(Vector::new(1, 1));
+
+            assert_eq!(Err(Occupied(Vector::new(1, 1))), validate_board(&board))
+        }
+
+        #[test]
+        fn validate_reason_obstacle_out_of_bounds() {
+            let board = empty_board()
+                .with_robot(Robot::new(Vector::new(1, 1), North))
+                .with_obstacle_at(Vector::new(1, 7));
+
+            assert_eq!(Err(OutOfBounds(Vector::new(1, 7))), validate_board(&board))
+        }
+
+        #[test]
+        fn validate_reason_diagonal_facing_on_four_way_board() {
+            let board = empty_board().with_robot(Robot::new(Vector::new(1, 1), NorthEast));
+
+            assert_eq!(Err(UnsupportedFacing(NorthEast)), validate_board(&board))
+        }
+
+        #[test]
+        fn validate_diagonal_facing_on_eight_way_board() {
+            let board = empty_board()
+                .with_grid(eight_way())
+                .with_robot(Robot::new(Vector::new(1, 1), NorthEast));
+
+            assert_eq!(Ok(()), validate_board(&board))
+        }
+
+        #[test]
+        fn validate_reason_east_facing_on_hex_board() {
+            let board = empty_board()
+                .with_grid(hex())
+                .with_robot(Robot::new(Vector::new(1, 1), East));
+
+            assert_eq!(Err(UnsupportedFacing(East)), validate_board(&board))
+        }
+
+        #[test]
+        fn validate_reason_out_of_bounds_on_hex_board() {
+            let board = empty_board()
+                .with_grid(hex())
+                .with_robot(Robot::new(Vector::new(4, 1), North));
+
+            assert_eq!(Err(OutOfBounds(Vector::new(4, 1))), validate_board(&board))
+        }
+
+        #[test]
+        fn validate_reason_robot_over_pit() {
+            let board = empty_board()
+                .with_terrain_at(Vector::new(1, 1), Terrain::Pit)
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(Err(OverPit(Vector::new(1, 1))), validate_board(&board))
+        }
+
+        #[test]
+        fn validate_reason_robot_on_patrol() {
+            let board = empty_board()
+                .with_patrol(Patrol::new(vec![Vector::new(1, 1)]).unwrap())
+                .with_robot(Robot::new(Vector::new(1, 1), North));
+
+            assert_eq!(Err(Collision(Vector::new(1, 1))), validate_board(&board))
+        }
+    }
+
+    /// Random scripts run on random boards, each from a seed the assertion names, checking what
+    /// execution does both before and after the board is validated.
+    mod properties {
+        use crate::commands::Command;
+        use crate::game_model::{Board, EnergyModel, Item, Terrain};
+        use crate::geo::grid::Grid;
+        use crate::geo::RelativeDirection::{Left, Right};
+        use crate::geo::Vector;
+        use crate::random::SeededRandom;
+        use crate::test_support::{any_direction, turns_in_full_circle, DIRECTIONS};
+
+        use super::super::ExecutionError::OutOfBounds;
+        use super::super::{
+            apply_command, is_board_valid, update_board_from_command, validate_board,
+        };
+        use super::{eight_way, hex};
+
+        const CASES: u64 = 300;
+        const COMMANDS_PER_CASE: usize = 60;
+
+        /// A location on or just off a board of the given size.
+        fn any_location(random: &mut SeededRandom, size: i16) -> Vector {
+            let mut coordinate = || random.below(size as usize + 3) as i16 - 1;
+
+            Vector::new(coordinate(), coordinate())
+        }
+
+        /// A valid board from 1x1 to 6x6 on any grid, scattered with obstacles and, unless
+        /// `plain`, terrain, chargers, items, conveyors, teleporters and a battery.
+        fn any_board(random: &mut SeededRandom, plain: bool) -> (Board, i16) {
+            let size = random.below(6) as i16;
+            let mut board = Board::empty_with_corner(&Vector::new(size, size));
+
+            board = match random.below(3) {
+                0 => board,
+                1 => board.with_grid(eight_way()),
+                _ => board.with_grid(hex()),
+            };
+
+            for _ in 0..random.below(6) {
+                let location = any_location(random, size);
+
+                if board.contains(&location) {
+                    board = board.with_obstacle_at(location);
+                }
+            }
+
+            if plain {
+                return (board, size);
+            }
+
+            for _ in 0..random.below(6) {
+                let location = any_location(random, size);
+
+                if !board.contains(&location) {
+                    continue;
+                }
+
+                board = match random.below(7) {
+                    0 => board.with_terrain_at(location, Terrain::Carpet),
+                    1 => board.with_terrain_at(location, Terrain::Ice),
+                    2 => board.with_terrain_at(location, Terrain::Pit),
+                    3 => board.with_charger_at(location),
+                    4 => board.with_item_at(location, Item('k')),
+                    5 => board.with_conveyor_at(location, DIRECTIONS[random.below(4)]),
+                    _ => board.with_teleporters_at(location, any_location(random, size)),
+                };
+            }
+
+            if random.below(2) == 0 {
+                board = board.with_energy_model(EnergyModel {
+                    capacity: 1 + random.below(20) as u32,
+                    move_cost: random.below(3) as u32,
+                    rotate_cost: random.below(2) as u32,
+                    carpet_cost: random.below(3) as u32,
+                });
+            }
+
+            (board, size)
+        }
+
+        fn any_command(random: &mut SeededRandom, size: i16) -> Command {
+            match random.below(12) {
+                0 | 1 => Command::Place {
+                    location: any_location(random, size),
+                    facing: any_direction(random),
+                },
+                2..=4 => Command::Move,
+                5 => Command::Rotate(Left),
+                6 => Command::Rotate(Right),
+                7 => Command::PlaceObjectAt(any_location(random, size)),
+                8 => Command::RemoveObjectAt(any_location(random, size)),
+                9 => Command::Pick,
+                10 => Command::Drop,
+                _ => Command::Queue(Box::new(Command::Move)),
+            }
+        }
+
+        #[test]
+        fn commands_are_applied_exactly_when_their_update_is_valid() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let (mut board, size) = any_board(&mut random, false);
+
+                for _ in 0..COMMANDS_PER_CASE {
+                    let command = any_command(&mut random, size);
+                    let update = update_board_from_command(&board, &command)
+                        .and_then(|next_board| validate_board(&next_board).map(|_| next_board));
+                    let (next_board, outcome) = apply_command(&board, &command);
+
+                    assert_eq!(
+                        update.is_ok(),
+                        outcome.is_ok(),
+                        "seed {}: {:?}",
+                        seed,
+                        command
+                    );
+
+                    if let Ok(updated_board) = update {
+                        assert_eq!(
+                            updated_board.without_history(),
+                            next_board.without_history(),
+                            "seed {}: {:?}",
+                            seed,
+                            command
+                        );
+                    }
+
+                    assert!(is_board_valid(&next_board), "seed {}: {:?}", seed, command);
+
+                    board = next_board;
+                }
+            }
+        }
+
+        #[test]
+        fn move_off_the_board_is_rejected_as_out_of_bounds() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let (mut board, size) = any_board(&mut random, false);
+
+                for _ in 0..COMMANDS_PER_CASE {
+                    let command = any_command(&mut random, size);
+                    let (next_board, outcome) = apply_command(&board, &command);
+
+                    if let (Command::Move, Ok(Some(robot))) = (
+                        &command,
+                        update_board_from_command(&board, &command).map(|moved| moved.robot),
+                    ) {
+                        if !board.contains(&robot.location) {
+                            assert_eq!(Err(OutOfBounds(robot.location)), outcome, "seed {}", seed);
+                            assert_eq!(board.robot, next_board.robot, "seed {}", seed);
+                        }
+                    }
+
+                    board = next_board;
+                }
+            }
+        }
+
+        #[test]
+        fn move_on_a_plain_board_goes_one_step_or_nowhere() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let (mut board, size) = any_board(&mut random, true);
+
+                for _ in 0..COMMANDS_PER_CASE {
+                    let command = any_command(&mut random, size);
+                    let next_board = apply_command(&board, &command).0;
+
+                    if let (Command::Move, Some(before), Some(after)) =
+                        (&command, &board.robot, &next_board.robot)
+                    {
+                        let stepped = board.grid.neighbour(&before.location, before.facing);
+
+                        assert!(
+                            after.location == before.location || Some(after.location) == stepped,
+                            "seed {}",
+                            seed
+                        );
+                    }
+
+                    board = next_board;
+                }
+            }
+        }
+
+        #[test]
+        fn rejected_commands_leave_the_robot_alone() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let (mut board, size) = any_board(&mut random, false);
+
+                for _ in 0..COMMANDS_PER_CASE {
+                    let command = any_command(&mut random, size);
+                    let (next_board, outcome) = apply_command(&board, &command);
+
+                    if outcome.is_err() {
+                        assert_eq!(board.robot, next_board.robot, "seed {}", seed);
+                    }
+
+                    board = next_board;
+                }
+            }
+        }
+
+        #[test]
+        fn turning_round_in_a_full_circle_changes_nothing() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let (mut board, size) = any_board(&mut random, true);
+
+                for _ in 0..COMMANDS_PER_CASE / 4 {
+                    board = apply_command(&board, &any_command(&mut random, size)).0;
+                }
+
+                let circle = turns_in_full_circle(&board.grid);
+                let turn = Command::Rotate([Left, Right][random.below(2)]);
+                let turned_board =
+                    (0..circle).fold(board.clone(), |board, _| apply_command(&board, &turn).0);
+
+                assert_eq!(board.robot, turned_board.robot, "seed {}", seed);
+            }
+        }
+
+        #[test]
+        fn left_then_right_changes_nothing() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let (mut board, size) = any_board(&mut random, true);
+
+                for _ in 0..COMMANDS_PER_CASE / 4 {
+                    board = apply_command(&board, &any_command(&mut random, size)).0;
+                }
+
+                let turned_board = [Left, Right].iter().fold(board.clone(), |board, turn| {
+                    apply_command(&board, &Command::Rotate(*turn)).0
+                });
+
+                assert_eq!(board.robot, turned_board.robot, "seed {}", seed);
+            }
+        }
     }
 }
@@ -0,0 +1,297 @@
+use crate::commands::parsing::{parse_line, ParsingError};
+use crate::commands::Command;
+use crate::error::RobotError;
+use crate::game_execution::{apply_command, ExecutionError};
+use crate::game_model::Board;
+use crate::session::{board_state, Entry};
+
+/// Something told about each step the simulator takes, such as a logger, a metrics counter or a
+/// visualiser. Every callback does nothing unless overridden.
+pub trait Observer {
+    fn on_command_parsed(&mut self, _input: &str, _command: &Command) {}
+
+    fn on_parse_failed(&mut self, _input: &str, _error: &ParsingError) {}
+
+    /// After a command changed the board, not counting its history.
+    fn on_board_changed(&mut self, _old: &Board, _new: &Board) {}
+
+    fn on_command_rejected(&mut self, _command: &Command, _reason: &ExecutionError) {}
+
+    fn on_output(&mut self, _output: &str) {}
+}
+
+/// Runs lines of input against a board, as the main loop does, telling every registered observer
+/// what happens in the order they were added.
+pub struct Simulator {
+    board: Board,
+    observers: Vec<Box<dyn Observer>>,
+}
+
+impl Simulator {
+    pub fn new(board: &Board) -> Simulator {
+        Simulator {
+            board: board.clone(),
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn into_board(self) -> Board {
+        self.board
+    }
+
+    /// Parses and runs one line of input, returning what happened as a recording writes it, and
+    /// the output or exactly what went wrong. A command that fails, or leaves the board invalid,
+    /// leaves the board as it was apart from its history.
+    pub fn run_line(&mut self, input: &str) -> (Entry, Result<Option<String>, RobotError>) {
+        let command = match parse_line(input) {
+            Ok(command) => command,
+            Err((parsing_error, span)) => {
+                for observer in &mut self.observers {
+                    observer.on_parse_failed(input, &parsing_error);
+                }
+
+                let entry = Entry {
+                    input: input.to_string(),
+                    command: Err(parsing_error.to_string()),
+                    output: None,
+                    error: None,
+                    valid: true,
+                    state: board_state(&self.board),
+                };
+
+                return (entry, Err(RobotError::parsing(input, span, parsing_error)));
+            }
+        };
+
+        for observer in &mut self.observers {
+            observer.on_command_parsed(input, &command);
+        }
+
+        let (next_board, outcome) = apply_command(&self.board, &command);
+
+        if let Ok(Some(output)) = &outcome {
+            for observer in &mut self.observers {
+                observer.on_output(output);
+            }
+        }
+
+        if let Err(execution_error) = &outcome {
+            for observer in &mut self.observers {
+                observer.on_command_rejected(&command, execution_error);
+            }
+        }
+
+        if next_board.without_history() != self.board.without_history() {
+            for observer in &mut self.observers {
+                observer.on_board_changed(&self.board, &next_board);
+            }
+        }
+
+        let (output, error) = match &outcome {
+            Ok(output) => (output.clone(), None),
+            Err(execution_error) => (None, Some(execution_error.to_string())),
+        };
+
+        let entry = Entry {
+            input: input.to_string(),
+            command: Ok(format!("{:?}", command)),
+            output,
+            valid: error.is_none(),
+            error,
+            state: board_state(&next_board),
+        };
+
+        self.board = next_board;
+
+        (entry, outcome.map_err(RobotError::from))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::commands::parsing::ParsingError;
+    use crate::commands::Command;
+    use crate::error::RobotError::{Execution, Parse};
+    use crate::game_execution::simulator::{Observer, Simulator};
+    use crate::game_execution::ExecutionError;
+    use crate::game_execution::ExecutionError::OutOfBounds;
+    use crate::game_model::Board;
+    use crate::geo::Vector;
+
+    /// Writes down every callback it gets, where a clone kept by the test can read them.
+    #[derive(Default, Clone)]
+    struct Log {
+        events: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Log {
+        fn events(&self) -> Vec<String> {
+            self.events.borrow().clone()
+        }
+    }
+
+    impl Observer for Log {
+        fn on_command_parsed(&mut self, input: &str, _command: &Command) {
+            self.events.borrow_mut().push(format!("PARSED {}", input));
+        }
+
+        fn on_parse_failed(&mut self, input: &str, error: &ParsingError) {
+            self.events
+                .borrow_mut()
+                .push(format!("FAILED {} {}", input, error));
+        }
+
+        fn on_board_changed(&mut self, old: &Board, new: &Board) {
+            let location = |board: &Board| {
+                board
+                    .robot
+                    .as_ref()
+                    .map_or("-".to_string(), |robot| robot.location.to_string())
+            };
+
+            self.events
+                .borrow_mut()
+                .push(format!("CHANGED {} {}", location(old), location(new)));
+        }
+
+        fn on_command_rejected(&mut self, command: &Command, reason: &ExecutionError) {
+            self.events
+                .borrow_mut()
+                .push(format!("REJECTED {:?} {}", command, reason));
+        }
+
+        fn on_output(&mut self, output: &str) {
+            self.events.borrow_mut().push(format!("OUTPUT {}", output));
+        }
+    }
+
+    /// Counts rejected commands and nothing else.
+    #[derive(Default, Clone)]
+    struct Rejections(Rc<RefCell<usize>>);
+
+    impl Observer for Rejections {
+        fn on_command_rejected(&mut self, _command: &Command, _reason: &ExecutionError) {
+            *self.0.borrow_mut() += 1;
+        }
+    }
+
+    fn board() -> Board {
+        Board::empty_with_corner(&Vector::new(4, 4))
+    }
+
+    #[test]
+    fn run_line_without_observers() {
+        let mut simulator = Simulator::new(&board());
+
+        assert!(simulator.run_line("PLACE 0,0,NORTH").1.unwrap().is_none());
+        assert_eq!(
+            Some("0,0,NORTH".to_string()),
+            simulator.run_line("REPORT").1.unwrap()
+        );
+        assert!(simulator.board().robot.is_some());
+    }
+
+    #[test]
+    fn run_line_entry() {
+        let mut simulator = Simulator::new(&board());
+        simulator.run_line("PLACE 0,4,NORTH").1.unwrap();
+
+        let (entry, _) = simulator.run_line("MOVE");
+
+        assert_eq!(Ok("Move".to_string()), entry.command);
+        assert_eq!(
+            Some("Location is out of bounds: 0,5".to_string()),
+            entry.error
+        );
+        assert!(!entry.valid);
+    }
+
+    #[test]
+    fn observer_command_and_output() {
+        let log = Log::default();
+        let mut simulator = Simulator::new(&board());
+        simulator.add_observer(Box::new(log.clone()));
+
+        simulator.run_line("PLACE 0,0,NORTH").1.unwrap();
+        simulator.run_line("REPORT").1.unwrap();
+
+        assert_eq!(
+            vec![
+                "PARSED PLACE 0,0,NORTH",
+                "CHANGED - 0,0",
+                "PARSED REPORT",
+                "OUTPUT 0,0,NORTH",
+            ],
+            log.events()
+        );
+    }
+
+    #[test]
+    fn observer_rejected_command() {
+        let log = Log::default();
+        let mut simulator = Simulator::new(&board());
+        simulator.run_line("PLACE 0,4,NORTH").1.unwrap();
+        simulator.add_observer(Box::new(log.clone()));
+
+        let (_, result) = simulator.run_line("MOVE");
+
+        assert!(matches!(result, Err(Execution(OutOfBounds(_)))));
+        assert_eq!(
+            Some(Vector::new(0, 4)),
+            simulator.board().robot.as_ref().map(|robot| robot.location)
+        );
+        assert_eq!(
+            vec![
+                "PARSED MOVE",
+                "REJECTED Move Location is out of bounds: 0,5"
+            ],
+            log.events()
+        );
+    }
+
+    #[test]
+    fn observer_parse_failure() {
+        let log = Log::default();
+        let mut simulator = Simulator::new(&board());
+        simulator.add_observer(Box::new(log.clone()));
+
+        assert!(matches!(simulator.run_line("JUMP").1, Err(Parse { .. })));
+        assert_eq!(vec!["FAILED JUMP Unrecognised command: JUMP"], log.events());
+    }
+
+    #[test]
+    fn observer_every_one() {
+        let first = Rejections::default();
+        let second = Rejections::default();
+        let log = Log::default();
+        let mut simulator = Simulator::new(&board());
+        simulator.add_observer(Box::new(first.clone()));
+        simulator.add_observer(Box::new(log.clone()));
+        simulator.add_observer(Box::new(second.clone()));
+
+        for input in &["PLACE 0,4,NORTH", "MOVE", "RIGHT", "MOVE", "LEFT", "MOVE"] {
+            let _ = simulator.run_line(input);
+        }
+
+        assert_eq!(2, *first.0.borrow());
+        assert_eq!(2, *second.0.borrow());
+        assert_eq!(
+            2,
+            log.events()
+                .iter()
+                .filter(|event| event.starts_with("REJECTED"))
+                .count()
+        );
+    }
+}
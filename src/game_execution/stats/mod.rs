@@ -0,0 +1,276 @@
+use crate::commands::Command;
+use crate::game_execution::ExecutionError::{
+    Collision, CornerBlocked, InventoryEmpty, NoItemAt, NoObstacleAt, NoRobot, NoSuchLevel,
+    Occupied, OutOfBounds, OutOfEnergy, OutOfRange, OutOfTicks, OverPit, TooManyTeleporters,
+    TooManyTicks, UnsupportedFacing,
+};
+use crate::game_execution::{annotated_map_for, ExecutionError};
+use crate::game_model::{Board, History};
+use crate::geo::Vector;
+
+/// The board the command left, with the command added to its history, along with the reason a
+/// MOVE was rejected. What the robot did is added as it happens, by `moved`, `turned`, `placed`
+/// and `entered`.
+pub(crate) fn record(
+    board: &Board,
+    command: &Command,
+    outcome: &Result<Board, ExecutionError>,
+) -> Board {
+    let recorded_board = match outcome {
+        Ok(next_board) => next_board.clone(),
+        Err(error) => rejected(board, command, error),
+    };
+
+    let mut history = recorded_board.history.clone();
+    history.commands += 1;
+
+    recorded_board.with_history(history)
+}
+
+/// The board with `command` counted as rejected for `error`, if it is a MOVE.
+pub(crate) fn rejected(board: &Board, command: &Command, error: &ExecutionError) -> Board {
+    match command {
+        Command::Move => {
+            let mut history = board.history.clone();
+            *history.rejected_moves.entry(reason(error)).or_insert(0) += 1;
+
+            board.with_history(history)
+        }
+        _ => board.clone(),
+    }
+}
+
+/// `history` after a MOVE that took the robot into each of `cells`.
+pub(crate) fn moved(history: &History, cells: &[Vector]) -> History {
+    let mut history = entered(history, cells);
+    history.applied_moves += 1;
+
+    history
+}
+
+/// `history` after the robot was carried into each of `cells`.
+pub(crate) fn entered(history: &History, cells: &[Vector]) -> History {
+    let mut history = history.clone();
+
+    for cell in cells {
+        *history.visits.entry(*cell).or_insert(0) += 1;
+    }
+
+    history.distance += cells.len() as u32;
+    history.last_location = cells.last().copied().or(history.last_location);
+
+    history
+}
+
+pub(crate) fn turned(history: &History) -> History {
+    History {
+        turns: history.turns + 1,
+        ..history.clone()
+    }
+}
+
+/// `history` with the robot in `location`, without it having moved there.
+pub(crate) fn placed(history: &History, location: Vector) -> History {
+    let mut history = history.clone();
+
+    *history.visits.entry(location).or_insert(0) += 1;
+    history.last_location = Some(location);
+
+    history
+}
+
+fn reason(error: &ExecutionError) -> &'static str {
+    match error {
+        OutOfBounds(_) => "OUT OF BOUNDS",
+        Occupied(_) => "OCCUPIED",
+        NoObstacleAt(_) => "NO OBSTACLE",
+        CornerBlocked(_) => "CORNER BLOCKED",
+        UnsupportedFacing(_) => "UNSUPPORTED FACING",
+        NoSuchLevel(_) => "NO SUCH LEVEL",
+        OutOfEnergy { .. } => "OUT OF ENERGY",
+        NoItemAt(_) => "NO ITEM",
+        InventoryEmpty => "INVENTORY EMPTY",
+        OverPit(_) => "PIT",
+        Collision(_) => "COLLISION",
+        OutOfRange(_) => "OUT OF RANGE",
+        TooManyTeleporters => "TOO MANY TELEPORTERS",
+        NoRobot => "NO ROBOT",
+        TooManyTicks(_) => "TOO MANY TICKS",
+        OutOfTicks(_) => "OUT OF TICKS",
+    }
+}
+
+/// The STATS output: counts from the robot's history, then a map of its level with each cell it
+/// has been in showing how many times, or `+` for more than nine.
+pub(crate) fn stats_report(board: &Board) -> String {
+    let history = &board.history;
+    let visits = &history.visits;
+
+    let rejected: u32 = history.rejected_moves.values().sum();
+
+    let mut lines: Vec<String> = vec![
+        format!("COMMANDS {}", history.commands),
+        format!(
+            "MOVES {} APPLIED {} REJECTED",
+            history.applied_moves, rejected
+        ),
+    ];
+
+    lines.extend(
+        history
+            .rejected_moves
+            .iter()
+            .map(|(reason, count)| format!("REJECTED {} {}", reason, count)),
+    );
+
+    lines.push(format!("DISTANCE {}", history.distance));
+    lines.push(format!("TURNS {}", history.turns));
+    lines.push(format!("VISITED {} CELLS", visits.len()));
+
+    let level = board
+        .robot
+        .as_ref()
+        .map(|robot| robot.location)
+        .or(history.last_location)
+        .map_or(board.bounds.bottom_left.z, |location| location.z);
+
+    lines.push(annotated_map_for(board, level, |location| {
+        visits
+            .get(location)
+            .map(|count| std::char::from_digit(*count, 10).unwrap_or('+'))
+    }));
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::commands::parsing::parse_command;
+    use crate::commands::Command;
+    use crate::game_execution::ExecutionError::OutOfBounds;
+    use crate::game_execution::{apply_command, output_from_command};
+    use crate::game_model::{Board, History, Robot, Terrain};
+    use crate::geo::Direction::*;
+    use crate::geo::Vector;
+
+    fn run(board: &Board, script: &[&str]) -> Board {
+        script.iter().fold(board.clone(), |board, line| {
+            apply_command(&board, &parse_command(line).unwrap()).0
+        })
+    }
+
+    #[test]
+    fn record_applied_and_rejected_moves() {
+        let board = run(
+            &Board::empty_with_corner(&Vector::new(4, 4)),
+            &["PLACE 0,3,NORTH", "MOVE", "MOVE", "RIGHT", "MOVE", "REPORT"],
+        );
+
+        assert_eq!(
+            History {
+                commands: 6,
+                applied_moves: 2,
+                rejected_moves: BTreeMap::from([("OUT OF BOUNDS", 1)]),
+                turns: 1,
+                distance: 2,
+                visits: HashMap::from([
+                    (Vector::new(0, 3), 1),
+                    (Vector::new(0, 4), 1),
+                    (Vector::new(1, 4), 1)
+                ]),
+                last_location: Some(Vector::new(1, 4)),
+            },
+            board.history
+        );
+    }
+
+    #[test]
+    fn record_rejected_move_keeps_board() {
+        let initial_board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_robot(Robot::new(Vector::new(0, 4), North));
+
+        let (board, outcome) = apply_command(&initial_board, &Command::Move);
+
+        assert_eq!(Err(OutOfBounds(Vector::new(0, 5))), outcome);
+        assert_eq!(initial_board.robot, board.robot);
+        assert_eq!(1, board.history.rejected_moves["OUT OF BOUNDS"]);
+    }
+
+    #[test]
+    fn record_cells_slid_over() {
+        let board = run(
+            &Board::empty_with_corner(&Vector::new(4, 4))
+                .with_terrain_at(Vector::new(1, 0), Terrain::Ice)
+                .with_terrain_at(Vector::new(2, 0), Terrain::Ice),
+            &["PLACE 0,0,EAST", "MOVE"],
+        );
+
+        assert_eq!(4, board.history.distance);
+        assert_eq!(Some(Vector::new(4, 0)), board.history.last_location);
+    }
+
+    #[test]
+    fn record_moves_made_by_cover() {
+        let board = run(
+            &Board::empty_with_corner(&Vector::new(0, 2)),
+            &["PLACE 0,0,NORTH", "COVER"],
+        );
+
+        assert_eq!(2, board.history.applied_moves);
+        assert_eq!(2, board.history.distance);
+    }
+
+    #[test]
+    fn record_queued_moves() {
+        let board = run(
+            &Board::empty_with_corner(&Vector::new(0, 1)),
+            &["PLACE 0,0,NORTH", "QUEUE MOVE", "QUEUE MOVE", "RUN"],
+        );
+
+        assert_eq!(1, board.history.applied_moves);
+        assert_eq!(1, board.history.rejected_moves["OUT OF BOUNDS"]);
+        assert_eq!(4, board.history.commands);
+    }
+
+    #[test]
+    fn record_conveyor_pushes() {
+        let board = run(
+            &Board::empty_with_corner(&Vector::new(2, 0)).with_conveyor_at(Vector::new(0, 0), East),
+            &["PLACE 0,0,NORTH", "TICK 1"],
+        );
+
+        assert_eq!(0, board.history.applied_moves);
+        assert_eq!(1, board.history.distance);
+        assert_eq!(Some(Vector::new(1, 0)), board.history.last_location);
+    }
+
+    #[test]
+    fn stats_report_with_heatmap() {
+        let board = run(
+            &Board::empty_with_corner(&Vector::new(2, 2)),
+            &[
+                "PLACE 0,0,EAST",
+                "MOVE",
+                "MOVE",
+                "MOVE",
+                "LEFT",
+                "MOVE",
+                "LEFT",
+                "MOVE",
+                "LEFT",
+                "MOVE",
+            ],
+        );
+
+        assert_eq!(
+            Some(
+                "COMMANDS 10\nMOVES 5 APPLIED 1 REJECTED\nREJECTED OUT OF BOUNDS 1\nDISTANCE 5\n\
+                 TURNS 3\nVISITED 5 CELLS\n000\n011\n1v1"
+                    .to_string()
+            ),
+            output_from_command(&board, &Command::Stats)
+        );
+    }
+}
@@ -0,0 +1,476 @@
+use crate::commands::Command;
+use crate::game_execution::ExecutionError::{OutOfTicks, TooManyTicks};
+use crate::game_execution::{
+    arrive, execute, is_blocked, note_pit, stats, step, teleport, validate_board, ExecutionError,
+    MoveEffect,
+};
+use crate::game_model::{Board, Robot};
+use crate::geo::Vector;
+
+/// The most ticks a single TICK can run.
+pub(crate) const MAX_TICKS: u32 = 10_000;
+
+/// Runs `ticks` ticks, or with `None` as many as it takes to empty the robot's queue, adding what
+/// the queued commands print to `output`.
+///
+/// Each tick moves every patrol a step, lets any conveyor under the robot push it one cell, and
+/// then runs the robot's oldest queued command. A queued command that fails is dropped and its
+/// error printed, as it would be if typed in directly, but a patrol running into the robot stops
+/// the run and fails the whole command. Once nothing is left that could change, the remaining
+/// ticks are counted without being run. Counting past the most ticks a board can count fails.
+pub(crate) fn run_ticks(
+    board: &Board,
+    ticks: Option<u32>,
+    output: &mut Vec<String>,
+) -> Result<Board, ExecutionError> {
+    if let Some(ticks) = ticks.filter(|ticks| *ticks > MAX_TICKS) {
+        return Err(TooManyTicks(ticks));
+    }
+
+    let mut board = board.clone();
+    let mut ticks_run = 0;
+
+    while ticks.map_or(has_queued_commands(&board), |ticks| ticks_run < ticks) {
+        if is_idle(&board) {
+            let remaining = ticks.map_or(0, |ticks| ticks - ticks_run);
+
+            return Ok(Board {
+                tick: board
+                    .tick
+                    .checked_add(remaining)
+                    .ok_or(OutOfTicks(board.tick))?,
+                ..board
+            });
+        }
+
+        board = run_tick(&board, output)?;
+        ticks_run += 1;
+    }
+
+    Ok(board)
+}
+
+fn has_queued_commands(board: &Board) -> bool {
+    board
+        .robot
+        .as_ref()
+        .is_some_and(|robot| !robot.queue.is_empty())
+}
+
+/// Whether a tick would change nothing but the tick count.
+fn is_idle(board: &Board) -> bool {
+    board.patrols.is_empty() && !has_queued_commands(board) && conveyor_destination(board).is_none()
+}
+
+fn run_tick(board: &Board, output: &mut Vec<String>) -> Result<Board, ExecutionError> {
+    if board.tick == u32::MAX {
+        return Err(OutOfTicks(board.tick));
+    }
+
+    let advanced_board = board.advanced();
+
+    validate_board(&advanced_board)?;
+
+    let conveyed_board = convey(&advanced_board, output);
+
+    let (robot, command) = match &conveyed_board.robot {
+        Some(robot) if !robot.queue.is_empty() => (robot, &robot.queue[0]),
+        _ => return Ok(conveyed_board),
+    };
+
+    let waiting_board = conveyed_board.with_robot(robot.with_queue(robot.queue[1..].to_vec()));
+    let mut command_output = Vec::new();
+
+    match execute(&waiting_board, command, &mut command_output)
+        .and_then(|next_board| validate_board(&next_board).map(|_| next_board))
+    {
+        Ok(next_board) => {
+            output.extend(command_output);
+            Ok(next_board)
+        }
+        Err(error) => {
+            output.push(format!("Tick {}: {}", waiting_board.tick, error));
+            Ok(stats::rejected(&waiting_board, command, &error))
+        }
+    }
+}
+
+/// Where the conveyor under the robot would push it, if it is on one with nothing in the way.
+fn conveyor_destination(board: &Board) -> Option<Vector> {
+    let robot = board.robot.as_ref()?;
+
+    board
+        .conveyor_locations
+        .get(&robot.location)
+        .filter(|direction| !is_blocked(board, &robot.location, **direction))
+        .and_then(|direction| step(board, &robot.location, *direction))
+}
+
+/// The board after the conveyor under the robot, if there is one, has pushed it a cell on. Being
+/// pushed onto a teleporter sends the robot through it, and a conveyor facing something in the way
+/// holds the robot where it is.
+fn convey(board: &Board, output: &mut Vec<String>) -> Board {
+    let (robot, next_location) = match (&board.robot, conveyor_destination(board)) {
+        (Some(robot), Some(next_location)) => (robot, next_location),
+        _ => return board.clone(),
+    };
+
+    let mut effects = vec![MoveEffect::Conveyed {
+        from: robot.location,
+        to: next_location,
+    }];
+    let mut path = vec![next_location];
+    let location = teleport(board, next_location, &mut effects, &mut path);
+
+    note_pit(board, location, &mut effects);
+    output.extend(effects.iter().map(MoveEffect::to_string));
+
+    arrive(
+        &board.with_history(stats::entered(&board.history, &path)),
+        robot,
+        location,
+    )
+}
+
+/// Adds `command` to the back of the robot's queue.
+pub(crate) fn queue_command(board: &Board, robot: &Robot, command: &Command) -> Board {
+    let mut queue = robot.queue.clone();
+    queue.push(command.clone());
+
+    board.with_robot(robot.with_queue(queue))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::Command;
+    use crate::game_execution::ticks::MAX_TICKS;
+    use crate::game_execution::ExecutionError::*;
+    use crate::game_execution::{output_from_command, update_board_from_command};
+    use crate::game_model::{Board, Patrol, Robot, Terrain};
+    use crate::geo::Direction::*;
+    use crate::geo::RelativeDirection::Right;
+    use crate::geo::Vector;
+
+    fn patrolled_board() -> Board {
+        Board::empty_with_corner(&Vector::new(4, 4)).with_patrol(
+            Patrol::new(vec![
+                Vector::new(3, 0),
+                Vector::new(3, 1),
+                Vector::new(3, 2),
+            ])
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn tick_moves_patrols() {
+        let command = Command::Tick(2);
+
+        let initial_board = patrolled_board();
+        let expected_board = Board {
+            patrols: vec![patrolled_board().patrols[0].advanced().advanced()],
+            tick: 2,
+            ..patrolled_board()
+        };
+
+        assert_eq!(
+            Ok(expected_board),
+            update_board_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_patrol_returns_to_start() {
+        let command = Command::Tick(3);
+
+        let initial_board = patrolled_board();
+
+        assert_eq!(
+            Ok(Vector::new(3, 0)),
+            update_board_from_command(&initial_board, &command)
+                .map(|board| board.patrols[0].location())
+        )
+    }
+
+    #[test]
+    fn tick_runs_one_queued_command() {
+        let command = Command::Tick(1);
+
+        let initial_board = patrolled_board().with_robot(
+            Robot::new(Vector::new(0, 0), North)
+                .with_queue(vec![Command::Move, Command::Rotate(Right)]),
+        );
+
+        assert_eq!(
+            Ok(Some(
+                Robot::new(Vector::new(0, 1), North).with_queue(vec![Command::Rotate(Right)])
+            )),
+            update_board_from_command(&initial_board, &command).map(|board| board.robot)
+        )
+    }
+
+    #[test]
+    fn run_empties_the_queue() {
+        let command = Command::Run;
+
+        let initial_board =
+            patrolled_board().with_robot(Robot::new(Vector::new(0, 0), North).with_queue(vec![
+                Command::Move,
+                Command::Rotate(Right),
+                Command::Move,
+            ]));
+
+        let board = update_board_from_command(&initial_board, &command).unwrap();
+
+        assert_eq!(Some(Robot::new(Vector::new(1, 1), East)), board.robot);
+        assert_eq!(3, board.tick)
+    }
+
+    #[test]
+    fn run_without_robot_does_nothing() {
+        let command = Command::Run;
+
+        let initial_board = patrolled_board();
+
+        assert_eq!(
+            Ok(patrolled_board()),
+            update_board_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_patrol_runs_into_robot() {
+        let command = Command::Tick(2);
+
+        let initial_board = patrolled_board().with_robot(Robot::new(Vector::new(3, 2), West));
+
+        assert_eq!(
+            Err(Collision(Vector::new(3, 2))),
+            update_board_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_robot_moving_into_patrol_is_dropped() {
+        let command = Command::Tick(1);
+
+        let initial_board = patrolled_board()
+            .with_robot(Robot::new(Vector::new(2, 1), East).with_queue(vec![Command::Move]));
+
+        assert_eq!(
+            Ok(Some(Robot::new(Vector::new(2, 1), East))),
+            update_board_from_command(&initial_board, &command).map(|board| board.robot)
+        );
+        assert_eq!(
+            Some("Tick 1: Collided with a patrol at: 3,1".to_string()),
+            output_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_output_from_queued_commands() {
+        let command = Command::Run;
+
+        let initial_board =
+            patrolled_board().with_robot(Robot::new(Vector::new(0, 0), North).with_queue(vec![
+                Command::Report,
+                Command::Move,
+                Command::Report,
+            ]));
+
+        assert_eq!(
+            Some("0,0,NORTH\n0,1,NORTH".to_string()),
+            output_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_conveyor_pushes_one_cell() {
+        let command = Command::Tick(1);
+
+        let initial_board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_conveyor_at(Vector::new(1, 2), East)
+            .with_conveyor_at(Vector::new(2, 2), East)
+            .with_robot(Robot::new(Vector::new(1, 2), North));
+
+        assert_eq!(
+            Ok(Some(Robot::new(Vector::new(2, 2), North))),
+            update_board_from_command(&initial_board, &command).map(|board| board.robot)
+        );
+        assert_eq!(
+            Some("Conveyed from 1,2 to 2,2".to_string()),
+            output_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_conveyors_carry_robot_along() {
+        let command = Command::Tick(3);
+
+        let initial_board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_conveyor_at(Vector::new(1, 2), East)
+            .with_conveyor_at(Vector::new(2, 2), East)
+            .with_robot(Robot::new(Vector::new(1, 2), North));
+
+        assert_eq!(
+            Ok(Some(Robot::new(Vector::new(3, 2), North))),
+            update_board_from_command(&initial_board, &command).map(|board| board.robot)
+        )
+    }
+
+    #[test]
+    fn tick_conveyor_stops_at_obstacle() {
+        let command = Command::Tick(1);
+
+        let initial_board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_conveyor_at(Vector::new(1, 2), East)
+            .with_obstacle_at(Vector::new(2, 2))
+            .with_robot(Robot::new(Vector::new(1, 2), North));
+
+        assert_eq!(
+            Ok(Some(Robot::new(Vector::new(1, 2), North))),
+            update_board_from_command(&initial_board, &command).map(|board| board.robot)
+        )
+    }
+
+    #[test]
+    fn tick_conveyor_stops_at_the_coordinate_limits() {
+        let command = Command::Tick(1);
+
+        let corner = Vector::new(i16::MAX, i16::MAX);
+        let initial_board = Board::empty_with_corner(&corner)
+            .with_conveyor_at(corner, North)
+            .with_robot(Robot::new(corner, East));
+
+        assert_eq!(
+            Ok(Some(Robot::new(corner, East))),
+            update_board_from_command(&initial_board, &command).map(|board| board.robot)
+        )
+    }
+
+    #[test]
+    fn tick_conveyors_pointing_at_each_other() {
+        let command = Command::Tick(2);
+
+        let initial_board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_conveyor_at(Vector::new(1, 2), East)
+            .with_conveyor_at(Vector::new(2, 2), West)
+            .with_robot(Robot::new(Vector::new(1, 2), North));
+
+        assert_eq!(
+            Some(
+                "Conveyed from 1,2 to 2,2\n\
+                 Conveyed from 2,2 to 1,2"
+                    .to_string()
+            ),
+            output_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_conveyor_onto_teleporter() {
+        let command = Command::Tick(1);
+
+        let initial_board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_conveyor_at(Vector::new(1, 2), East)
+            .with_teleporters_at(Vector::new(2, 2), Vector::new(4, 4))
+            .with_robot(Robot::new(Vector::new(1, 2), North));
+
+        assert_eq!(
+            Ok(Some(Robot::new(Vector::new(4, 4), North))),
+            update_board_from_command(&initial_board, &command).map(|board| board.robot)
+        );
+        assert_eq!(
+            Some(
+                "Conveyed from 1,2 to 2,2\n\
+                 Teleported from 2,2 to 4,4"
+                    .to_string()
+            ),
+            output_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_conveyor_into_pit() {
+        let command = Command::Tick(1);
+
+        let initial_board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_conveyor_at(Vector::new(1, 2), East)
+            .with_terrain_at(Vector::new(2, 2), Terrain::Pit)
+            .with_robot(Robot::new(Vector::new(1, 2), North).with_queue(vec![Command::Move]));
+
+        assert_eq!(
+            Ok(None),
+            update_board_from_command(&initial_board, &command).map(|board| board.robot)
+        )
+    }
+
+    #[test]
+    fn tick_conveys_before_running_the_queued_command() {
+        let command = Command::Tick(1);
+
+        let initial_board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_conveyor_at(Vector::new(1, 2), East)
+            .with_robot(Robot::new(Vector::new(1, 2), North).with_queue(vec![Command::Move]));
+
+        assert_eq!(
+            Ok(Some(Robot::new(Vector::new(2, 3), North))),
+            update_board_from_command(&initial_board, &command).map(|board| board.robot)
+        )
+    }
+
+    #[test]
+    fn tick_past_the_limit() {
+        let command = Command::Tick(MAX_TICKS + 1);
+
+        let initial_board = patrolled_board();
+
+        assert_eq!(
+            Err(TooManyTicks(MAX_TICKS + 1)),
+            update_board_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_past_the_most_a_board_can_count() {
+        let command = Command::Tick(MAX_TICKS);
+
+        let initial_board = Board {
+            tick: u32::MAX - 1,
+            ..Board::empty_with_corner(&Vector::new(4, 4))
+        };
+
+        assert_eq!(
+            Err(OutOfTicks(u32::MAX - 1)),
+            update_board_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_past_the_most_a_board_can_count_with_patrols() {
+        let command = Command::Tick(2);
+
+        let initial_board = Board {
+            tick: u32::MAX - 1,
+            ..patrolled_board()
+        };
+
+        assert_eq!(
+            Err(OutOfTicks(u32::MAX)),
+            update_board_from_command(&initial_board, &command)
+        )
+    }
+
+    #[test]
+    fn tick_counts_ticks_when_nothing_can_change() {
+        let command = Command::Tick(MAX_TICKS);
+
+        let initial_board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_robot(Robot::new(Vector::new(0, 0), North).with_queue(vec![Command::Move]));
+
+        let board = update_board_from_command(&initial_board, &command).unwrap();
+
+        assert_eq!(Some(Robot::new(Vector::new(0, 1), North)), board.robot);
+        assert_eq!(MAX_TICKS, board.tick)
+    }
+}
@@ -1,10 +1,18 @@
-use crate::geo::{Direction, Square, Vector};
-use std::collections::HashSet;
+use crate::commands::Command;
+use crate::geo::grid::{Grid, GridShape, SquareGrid};
+use crate::geo::{Compass, Direction, Square, Vector};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Robot {
     pub location: Vector,
     pub facing: Direction,
+    /// Remaining energy, or `None` if the robot is not limited by a battery.
+    pub energy: Option<u32>,
+    /// Items picked up, with the most recently picked up last.
+    pub inventory: Vec<Item>,
+    /// Commands waiting to be run one per tick, oldest first.
+    pub queue: Vec<Command>,
 }
 
 impl Robot {
@@ -12,18 +20,152 @@ impl Robot {
         Robot {
             location: position,
             facing,
+            energy: None,
+            inventory: Vec::new(),
+            queue: Vec::new(),
         }
     }
 
-    pub fn with_position(self: Robot, position: Vector) -> Robot {
+    pub fn with_position(self: &Robot, position: Vector) -> Robot {
         Robot {
             location: position,
-            ..self
+            ..self.clone()
+        }
+    }
+
+    pub fn with_facing(self: &Robot, facing: Direction) -> Robot {
+        Robot {
+            facing,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_energy(self: &Robot, energy: Option<u32>) -> Robot {
+        Robot {
+            energy,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_inventory(self: &Robot, inventory: Vec<Item>) -> Robot {
+        Robot {
+            inventory,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_queue(self: &Robot, queue: Vec<Command>) -> Robot {
+        Robot {
+            queue,
+            ..self.clone()
+        }
+    }
+}
+
+/// Something a robot can pick up and carry, named by a single lowercase letter.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub struct Item(pub char);
+
+impl Item {
+    /// Any lowercase letter but `v`, which MAP draws as a robot facing south.
+    pub fn is_name(name: char) -> bool {
+        name.is_ascii_lowercase() && name != 'v'
+    }
+}
+
+/// How much energy a battery holds and what each action costs.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct EnergyModel {
+    pub capacity: u32,
+    pub move_cost: u32,
+    pub rotate_cost: u32,
+    /// Extra energy on top of `move_cost` for moving onto carpet.
+    pub carpet_cost: u32,
+}
+
+impl EnergyModel {
+    /// The carpet cost when a battery is set up without one.
+    pub const DEFAULT_CARPET_COST: u32 = 1;
+}
+
+/// What a cell's floor is made of. Cells without terrain are `Floor`.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Terrain {
+    Floor,
+    /// Costs extra energy to move onto.
+    Carpet,
+    /// Keeps a robot sliding in the direction it moved until it is stopped.
+    Ice,
+    /// Destroys a robot that moves onto it.
+    Pit,
+}
+
+/// An obstacle that moves one step along a fixed route each tick, going back to the start of the
+/// route after the last step.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Patrol {
+    route: Vec<Vector>,
+    /// Index into `route` of where the obstacle is now.
+    position: usize,
+}
+
+impl Patrol {
+    /// A patrol starting at the first cell of `route`, or `None` if the route is empty.
+    pub fn new(route: Vec<Vector>) -> Option<Patrol> {
+        if route.is_empty() {
+            None
+        } else {
+            Some(Patrol { route, position: 0 })
         }
     }
 
-    pub fn with_facing(self: Robot, facing: Direction) -> Robot {
-        Robot { facing, ..self }
+    pub fn route(self: &Patrol) -> &[Vector] {
+        &self.route
+    }
+
+    pub fn location(self: &Patrol) -> Vector {
+        self.route[self.position]
+    }
+
+    pub fn advanced(self: &Patrol) -> Patrol {
+        Patrol {
+            position: (self.position + 1) % self.route.len(),
+            ..self.clone()
+        }
+    }
+}
+
+/// What the robot has done so far, for STATS.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct History {
+    pub commands: u32,
+    pub applied_moves: u32,
+    /// Rejected MOVEs, counted by the reason they were rejected.
+    pub rejected_moves: BTreeMap<&'static str, u32>,
+    pub turns: u32,
+    /// Cells entered by MOVEs and conveyors, including any slid or teleported through.
+    pub distance: u32,
+    /// How many times the robot has been in each cell, counting where it was placed.
+    pub visits: HashMap<Vector, u32>,
+    /// The cell the robot was last in.
+    pub last_location: Option<Vector>,
+}
+
+/// A cell that carries a robot moving onto it up or down to the same cell on the adjacent level.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Ramp {
+    Up,
+    Down,
+}
+
+impl Ramp {
+    /// Where the ramp leads, or `None` if the level would be past the limits of `i16`.
+    pub fn destination(self: Ramp, location: &Vector) -> Option<Vector> {
+        match self {
+            Ramp::Up => location.z.checked_add(1),
+            Ramp::Down => location.z.checked_sub(1),
+        }
+        .map(|z| location.with_level(z))
     }
 }
 
@@ -32,6 +174,22 @@ pub struct Board {
     pub bounds: Square,
     pub robot: Option<Robot>,
     pub obstacle_locations: HashSet<Vector>,
+    pub ramp_locations: HashMap<Vector, Ramp>,
+    pub charger_locations: HashSet<Vector>,
+    pub item_locations: HashMap<Vector, Item>,
+    pub goal_locations: HashSet<Vector>,
+    pub terrain: HashMap<Vector, Terrain>,
+    /// Linked pairs of cells, in the order they were placed. A robot moving onto one is carried
+    /// to the other.
+    pub teleporter_pairs: Vec<(Vector, Vector)>,
+    /// Cells that push a robot on them one cell in their direction.
+    pub conveyor_locations: HashMap<Vector, Direction>,
+    pub patrols: Vec<Patrol>,
+    /// Ticks run so far.
+    pub tick: u32,
+    pub history: History,
+    pub energy_model: Option<EnergyModel>,
+    pub grid: GridShape,
 }
 
 impl Board {
@@ -40,17 +198,48 @@ impl Board {
             bounds: Square::with_corners(&Vector::new(0, 0), corner),
             robot: None,
             obstacle_locations: HashSet::new(),
+            ramp_locations: HashMap::new(),
+            charger_locations: HashSet::new(),
+            item_locations: HashMap::new(),
+            goal_locations: HashSet::new(),
+            terrain: HashMap::new(),
+            teleporter_pairs: Vec::new(),
+            conveyor_locations: HashMap::new(),
+            patrols: Vec::new(),
+            tick: 0,
+            history: History::default(),
+            energy_model: None,
+            grid: GridShape::Square(SquareGrid {
+                compass: Compass::FourWay,
+            }),
         }
     }
 
     pub fn with_robot(self: &Board, robot: Robot) -> Board {
         Board {
-            bounds: self.bounds,
             robot: Some(robot),
-            obstacle_locations: self.obstacle_locations.clone(),
+            ..self.clone()
+        }
+    }
+
+    pub fn without_robot(self: &Board) -> Board {
+        Board {
+            robot: None,
+            ..self.clone()
         }
     }
 
+    pub fn with_grid(self: &Board, grid: GridShape) -> Board {
+        Board {
+            grid,
+            ..self.clone()
+        }
+    }
+
+    pub fn contains(self: &Board, location: &Vector) -> bool {
+        self.grid.contains(&self.bounds, location)
+    }
+
     pub fn with_obstacle_at(self: &Board, obstacle_location: Vector) -> Board {
         let mut new_obstacle_locations = self.obstacle_locations.clone();
 
@@ -58,7 +247,188 @@ impl Board {
 
         Board {
             obstacle_locations: new_obstacle_locations,
-            ..*self
+            ..self.clone()
+        }
+    }
+
+    pub fn without_obstacle_at(self: &Board, obstacle_location: Vector) -> Board {
+        let mut new_obstacle_locations = self.obstacle_locations.clone();
+
+        new_obstacle_locations.remove(&obstacle_location);
+
+        Board {
+            obstacle_locations: new_obstacle_locations,
+            ..self.clone()
+        }
+    }
+
+    pub fn without_obstacles(self: &Board) -> Board {
+        Board {
+            obstacle_locations: HashSet::new(),
+            ..self.clone()
         }
     }
+
+    pub fn with_ramp_at(self: &Board, ramp_location: Vector, ramp: Ramp) -> Board {
+        let mut new_ramp_locations = self.ramp_locations.clone();
+
+        new_ramp_locations.insert(ramp_location, ramp);
+
+        Board {
+            ramp_locations: new_ramp_locations,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_charger_at(self: &Board, charger_location: Vector) -> Board {
+        let mut new_charger_locations = self.charger_locations.clone();
+
+        new_charger_locations.insert(charger_location);
+
+        Board {
+            charger_locations: new_charger_locations,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_item_at(self: &Board, item_location: Vector, item: Item) -> Board {
+        let mut new_item_locations = self.item_locations.clone();
+
+        new_item_locations.insert(item_location, item);
+
+        Board {
+            item_locations: new_item_locations,
+            ..self.clone()
+        }
+    }
+
+    pub fn without_item_at(self: &Board, item_location: Vector) -> Board {
+        let mut new_item_locations = self.item_locations.clone();
+
+        new_item_locations.remove(&item_location);
+
+        Board {
+            item_locations: new_item_locations,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_goal_at(self: &Board, goal_location: Vector) -> Board {
+        let mut new_goal_locations = self.goal_locations.clone();
+
+        new_goal_locations.insert(goal_location);
+
+        Board {
+            goal_locations: new_goal_locations,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_terrain_at(self: &Board, location: Vector, terrain: Terrain) -> Board {
+        let mut new_terrain = self.terrain.clone();
+
+        match terrain {
+            Terrain::Floor => new_terrain.remove(&location),
+            _ => new_terrain.insert(location, terrain),
+        };
+
+        Board {
+            terrain: new_terrain,
+            ..self.clone()
+        }
+    }
+
+    pub fn terrain_at(self: &Board, location: &Vector) -> Terrain {
+        self.terrain
+            .get(location)
+            .copied()
+            .unwrap_or(Terrain::Floor)
+    }
+
+    pub fn with_teleporters_at(self: &Board, first: Vector, second: Vector) -> Board {
+        let mut new_teleporter_pairs = self.teleporter_pairs.clone();
+
+        new_teleporter_pairs.push((first, second));
+
+        Board {
+            teleporter_pairs: new_teleporter_pairs,
+            ..self.clone()
+        }
+    }
+
+    /// The other end of the teleporter at `location`, if there is one.
+    pub fn teleporter_destination(self: &Board, location: &Vector) -> Option<Vector> {
+        self.teleporter_pairs
+            .iter()
+            .find_map(|(first, second)| match location {
+                _ if location == first => Some(*second),
+                _ if location == second => Some(*first),
+                _ => None,
+            })
+    }
+
+    pub fn with_conveyor_at(
+        self: &Board,
+        conveyor_location: Vector,
+        direction: Direction,
+    ) -> Board {
+        let mut new_conveyor_locations = self.conveyor_locations.clone();
+
+        new_conveyor_locations.insert(conveyor_location, direction);
+
+        Board {
+            conveyor_locations: new_conveyor_locations,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_patrol(self: &Board, patrol: Patrol) -> Board {
+        let mut new_patrols = self.patrols.clone();
+
+        new_patrols.push(patrol);
+
+        Board {
+            patrols: new_patrols,
+            ..self.clone()
+        }
+    }
+
+    pub fn has_patrol_at(self: &Board, location: &Vector) -> bool {
+        self.patrols
+            .iter()
+            .any(|patrol| patrol.location() == *location)
+    }
+
+    /// The board one tick later, with every patrol moved on a step. The tick count stops at the
+    /// most it can count.
+    pub fn advanced(self: &Board) -> Board {
+        Board {
+            patrols: self.patrols.iter().map(Patrol::advanced).collect(),
+            tick: self.tick.saturating_add(1),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_history(self: &Board, history: History) -> Board {
+        Board {
+            history,
+            ..self.clone()
+        }
+    }
+
+    /// The board without its history, for searches that copy it at every step they try.
+    pub fn without_history(self: &Board) -> Board {
+        self.with_history(History::default())
+    }
+
+    pub fn with_energy_model(self: &Board, energy_model: EnergyModel) -> Board {
+        Board {
+            energy_model: Some(energy_model),
+            ..self.clone()
+        }
+    }
+
+    pub fn is_stacked(self: &Board) -> bool {
+        self.bounds.is_stacked()
+    }
 }
@@ -0,0 +1,466 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::analysis::connected_components;
+use crate::game_model::{Board, Terrain};
+use crate::geo::grid::Grid;
+use crate::geo::Direction::*;
+use crate::geo::{Square, Vector};
+use crate::random::SeededRandom;
+
+/// How to lay out generated obstacles.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Layout {
+    /// Each cell is an obstacle with the given percentage chance.
+    Random { density: u8 },
+    /// A recursive-backtracker maze of one-cell passages, rooted at every other row and column.
+    Maze,
+    /// Open rectangular rooms joined by one-cell corridors.
+    Rooms,
+}
+
+/// Replaces the board's obstacles with a new set laid out by `layout` on every level, the same
+/// for the same seed. Mazes and rooms are laid out in rows and columns, so suit square grids.
+///
+/// Cells holding the robot or anything else, such as items, ramps or goals, are always left
+/// free. With `connected`, obstacles are then cleared along the shortest lines joining each
+/// separate area of free space on a level to the robot's, or to the largest on that level if the
+/// robot is elsewhere. These lines never leave their level, so levels are joined to each other
+/// only by the ramps already there: a stacked board without ramps has each level in one piece of
+/// its own.
+pub fn generate(board: &Board, layout: Layout, seed: u64, connected: bool) -> Board {
+    let mut random = SeededRandom::new(seed);
+    let bounds = board.bounds;
+
+    let obstacles: HashSet<Vector> = (bounds.bottom_left.z..=bounds.top_right.z)
+        .flat_map(|z| {
+            let level = Square::with_corners(
+                &bounds.bottom_left.with_level(z),
+                &bounds.top_right.with_level(z),
+            );
+
+            match layout {
+                Layout::Random { density } => random_obstacles(board, &level, density, &mut random),
+                Layout::Maze => maze_obstacles(&level, &mut random),
+                Layout::Rooms => room_obstacles(&level, &mut random),
+            }
+        })
+        .filter(|location| board.contains(location) && !is_protected(board, location))
+        .collect();
+
+    let generated = Board {
+        obstacle_locations: obstacles,
+        ..board.clone()
+    };
+
+    if connected {
+        connect(&generated)
+    } else {
+        generated
+    }
+}
+
+fn is_protected(board: &Board, location: &Vector) -> bool {
+    board
+        .robot
+        .as_ref()
+        .is_some_and(|robot| robot.location == *location)
+        || board.ramp_locations.contains_key(location)
+        || board.charger_locations.contains(location)
+        || board.item_locations.contains_key(location)
+        || board.goal_locations.contains(location)
+        || board.teleporter_destination(location).is_some()
+        || board.conveyor_locations.contains_key(location)
+        || board
+            .patrols
+            .iter()
+            .any(|patrol| patrol.route().contains(location))
+}
+
+fn random_obstacles(
+    board: &Board,
+    level: &Square,
+    density: u8,
+    random: &mut SeededRandom,
+) -> Vec<Vector> {
+    board
+        .grid
+        .layout(level)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|_| random.below(100) < density as usize)
+        .collect()
+}
+
+fn cells_of(level: &Square) -> Vec<Vector> {
+    (level.bottom_left.x..=level.top_right.x)
+        .flat_map(|x| {
+            (level.bottom_left.y..=level.top_right.y)
+                .map(move |y| Vector::at_level(x, y, level.bottom_left.z))
+        })
+        .collect()
+}
+
+// Coordinates are worked out in i32 here, as a board can span more than an i16 can count.
+
+/// How many cells there are from `low` to `high` inclusive.
+fn cells_across(low: i16, high: i16) -> usize {
+    (i32::from(high) - i32::from(low) + 1) as usize
+}
+
+/// `by` cells on from `base`, but no further than `limit`.
+fn offset_up_to(base: i16, by: usize, limit: i16) -> i16 {
+    (i64::from(base) + by as i64).min(i64::from(limit)) as i16
+}
+
+fn midpoint(first: i16, second: i16) -> i16 {
+    ((i32::from(first) + i32::from(second)) / 2) as i16
+}
+
+fn maze_obstacles(level: &Square, random: &mut SeededRandom) -> Vec<Vector> {
+    let origin = level.bottom_left;
+    let is_room = |location: &Vector| {
+        cells_across(origin.x, location.x) % 2 == 1 && cells_across(origin.y, location.y) % 2 == 1
+    };
+
+    let mut open: HashSet<Vector> = HashSet::new();
+    let mut stack: Vec<Vector> = vec![origin];
+
+    open.insert(origin);
+
+    while let Some(room) = stack.last().copied() {
+        let unvisited: Vec<Vector> = [North, South, East, West]
+            .iter()
+            .filter_map(|direction| room.translate(*direction)?.translate(*direction))
+            .filter(|next_room| {
+                level.contains(next_room) && is_room(next_room) && !open.contains(next_room)
+            })
+            .collect();
+
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let next_room = unvisited[random.below(unvisited.len())];
+        let wall = Vector::at_level(
+            midpoint(room.x, next_room.x),
+            midpoint(room.y, next_room.y),
+            room.z,
+        );
+
+        open.insert(wall);
+        open.insert(next_room);
+        stack.push(next_room);
+    }
+
+    cells_of(level)
+        .into_iter()
+        .filter(|location| !open.contains(location))
+        .collect()
+}
+
+fn room_obstacles(level: &Square, random: &mut SeededRandom) -> Vec<Vector> {
+    let width = cells_across(level.bottom_left.x, level.top_right.x);
+    let height = cells_across(level.bottom_left.y, level.top_right.y);
+    let attempts = (width * height) / 12 + 1;
+
+    let mut open: HashSet<Vector> = HashSet::new();
+    let mut centres: Vec<Vector> = Vec::new();
+
+    for _ in 0..attempts {
+        let room_width = 2 + random.below((width / 3).max(1));
+        let room_height = 2 + random.below((height / 3).max(1));
+        let left = offset_up_to(
+            level.bottom_left.x,
+            random.below(width.saturating_sub(room_width) + 1),
+            level.top_right.x,
+        );
+        let bottom = offset_up_to(
+            level.bottom_left.y,
+            random.below(height.saturating_sub(room_height) + 1),
+            level.top_right.y,
+        );
+
+        let room = Square::with_corners(
+            &Vector::at_level(left, bottom, level.bottom_left.z),
+            &Vector::at_level(
+                offset_up_to(left, room_width - 1, level.top_right.x),
+                offset_up_to(bottom, room_height - 1, level.top_right.y),
+                level.bottom_left.z,
+            ),
+        );
+
+        open.extend(cells_of(&room));
+        centres.push(Vector::at_level(
+            midpoint(room.bottom_left.x, room.top_right.x),
+            midpoint(room.bottom_left.y, room.top_right.y),
+            level.bottom_left.z,
+        ));
+    }
+
+    for pair in centres.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+
+        for x in from.x.min(to.x)..=from.x.max(to.x) {
+            open.insert(Vector::at_level(x, from.y, from.z));
+        }
+
+        for y in from.y.min(to.y)..=from.y.max(to.y) {
+            open.insert(Vector::at_level(to.x, y, from.z));
+        }
+    }
+
+    cells_of(level)
+        .into_iter()
+        .filter(|location| !open.contains(location))
+        .collect()
+}
+
+/// Clears obstacles until free space on each level is in one piece, or until no more pieces can
+/// be joined.
+fn connect(board: &Board) -> Board {
+    let mut board = board.clone();
+
+    loop {
+        let components = connected_components(&board);
+
+        for z in board.bounds.bottom_left.z..=board.bounds.top_right.z {
+            // Each component's cells on this level, largest component first.
+            let on_level: Vec<(usize, HashSet<Vector>)> = components
+                .iter()
+                .enumerate()
+                .map(|(index, component)| {
+                    let cells: HashSet<Vector> = component
+                        .iter()
+                        .filter(|location| location.z == z)
+                        .copied()
+                        .collect();
+
+                    (index, cells)
+                })
+                .filter(|(_, cells)| !cells.is_empty())
+                .collect();
+
+            if on_level.len() < 2 {
+                continue;
+            }
+
+            let main = board
+                .robot
+                .as_ref()
+                .and_then(|robot| {
+                    on_level
+                        .iter()
+                        .position(|(index, _)| components[*index].contains(&robot.location))
+                })
+                .unwrap_or(0);
+            let other = if main == 0 { 1 } else { 0 };
+
+            if let Some(tunnel) = tunnel(&board, &on_level[other].1, &on_level[main].1) {
+                for location in tunnel {
+                    board = board.without_obstacle_at(location);
+                }
+            }
+        }
+
+        if connected_components(&board).len() >= components.len() {
+            return board;
+        }
+    }
+}
+
+/// The cells on a shortest line from `from` to `to` through anything but pits, moving only in
+/// directions that cannot cut a corner.
+fn tunnel(board: &Board, from: &HashSet<Vector>, to: &HashSet<Vector>) -> Option<Vec<Vector>> {
+    let mut parents: HashMap<Vector, Option<Vector>> = HashMap::new();
+    let mut queue: VecDeque<Vector> = VecDeque::new();
+
+    let mut starts: Vec<&Vector> = from.iter().collect();
+    starts.sort_by_key(|location| (location.z, location.x, location.y));
+
+    for start in starts {
+        parents.insert(*start, None);
+        queue.push_back(*start);
+    }
+
+    while let Some(location) = queue.pop_front() {
+        if to.contains(&location) {
+            let mut path = Vec::new();
+            let mut current = Some(location);
+
+            while let Some(cell) = current {
+                path.push(cell);
+                current = parents[&cell];
+            }
+
+            return Some(path);
+        }
+
+        for direction in [
+            North, South, East, West, NorthEast, NorthWest, SouthEast, SouthWest,
+        ] {
+            if !board.grid.supports(&direction)
+                || !board.grid.corners(&location, direction).is_empty()
+            {
+                continue;
+            }
+
+            let next_location = match board.grid.neighbour(&location, direction) {
+                Some(next_location) => next_location,
+                None => continue,
+            };
+
+            if board.contains(&next_location)
+                && board.terrain_at(&next_location) != Terrain::Pit
+                && !parents.contains_key(&next_location)
+            {
+                parents.insert(next_location, Some(location));
+                queue.push_back(next_location);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::analysis::connected_components;
+    use crate::game_model::{Board, Item, Robot};
+    use crate::generation::{generate, Layout};
+    use crate::geo::Direction::*;
+    use crate::geo::{Square, Vector};
+
+    fn board_with_robot() -> Board {
+        Board::empty_with_corner(&Vector::new(8, 8))
+            .with_robot(Robot::new(Vector::new(3, 3), North))
+    }
+
+    #[test]
+    fn same_seed_same_layout() {
+        for layout in [Layout::Random { density: 40 }, Layout::Maze, Layout::Rooms] {
+            assert_eq!(
+                generate(&board_with_robot(), layout, 99, false),
+                generate(&board_with_robot(), layout, 99, false)
+            );
+        }
+    }
+
+    #[test]
+    fn generates_at_the_coordinate_limits() {
+        let board = Board {
+            bounds: Square::with_corners(
+                &Vector::new(i16::MAX - 6, i16::MIN),
+                &Vector::new(i16::MAX, i16::MIN + 6),
+            ),
+            ..Board::empty_with_corner(&Vector::new(0, 0))
+        }
+        .with_robot(Robot::new(Vector::new(i16::MAX, i16::MIN), North));
+
+        for layout in [Layout::Random { density: 40 }, Layout::Maze, Layout::Rooms] {
+            let generated = generate(&board, layout, 5, true);
+
+            assert!(generated
+                .obstacle_locations
+                .iter()
+                .all(|location| board.contains(location)));
+            assert_eq!(1, connected_components(&generated).len());
+        }
+    }
+
+    #[test]
+    fn different_seeds_different_layouts() {
+        assert_ne!(
+            generate(&board_with_robot(), Layout::Maze, 1, false).obstacle_locations,
+            generate(&board_with_robot(), Layout::Maze, 2, false).obstacle_locations
+        )
+    }
+
+    #[test]
+    fn random_with_no_density_is_empty() {
+        let board = generate(&board_with_robot(), Layout::Random { density: 0 }, 5, false);
+
+        assert_eq!(HashSet::new(), board.obstacle_locations)
+    }
+
+    #[test]
+    fn random_full_density_leaves_contents_free() {
+        let initial_board = board_with_robot()
+            .with_item_at(Vector::new(0, 0), Item('a'))
+            .with_goal_at(Vector::new(8, 8));
+
+        let board = generate(&initial_board, Layout::Random { density: 100 }, 5, false);
+
+        assert_eq!(81 - 3, board.obstacle_locations.len());
+        assert!(!board.obstacle_locations.contains(&Vector::new(3, 3)));
+        assert!(!board.obstacle_locations.contains(&Vector::new(0, 0)));
+        assert!(!board.obstacle_locations.contains(&Vector::new(8, 8)))
+    }
+
+    #[test]
+    fn generate_replaces_existing_obstacles() {
+        let initial_board = board_with_robot().with_obstacle_at(Vector::new(1, 1));
+
+        let board = generate(&initial_board, Layout::Random { density: 0 }, 5, false);
+
+        assert!(board.obstacle_locations.is_empty())
+    }
+
+    #[test]
+    fn maze_is_one_piece() {
+        let board = generate(
+            &Board::empty_with_corner(&Vector::new(8, 8)),
+            Layout::Maze,
+            3,
+            false,
+        );
+
+        assert_eq!(1, connected_components(&board).len());
+        assert!(!board.obstacle_locations.contains(&Vector::new(0, 0)))
+    }
+
+    #[test]
+    fn connected_rooms_are_one_piece() {
+        for seed in 0..10 {
+            let board = generate(&board_with_robot(), Layout::Rooms, seed, true);
+
+            assert_eq!(1, connected_components(&board).len());
+        }
+    }
+
+    #[test]
+    fn connected_stacked_levels_without_ramps_are_a_piece_each() {
+        let board = Board::empty_with_corner(&Vector::at_level(8, 8, 2))
+            .with_robot(Robot::new(Vector::new(3, 3), North));
+
+        for layout in [Layout::Random { density: 45 }, Layout::Rooms] {
+            for seed in 0..5 {
+                let components = connected_components(&generate(&board, layout, seed, true));
+
+                assert_eq!(3, components.len(), "{:?} seed {}", layout, seed);
+                assert!(components.iter().all(|component| {
+                    let z = component.iter().next().unwrap().z;
+                    component.iter().all(|location| location.z == z)
+                }));
+            }
+        }
+    }
+
+    #[test]
+    fn connected_random_fields_are_one_piece() {
+        for seed in 0..10 {
+            let board = generate(
+                &board_with_robot(),
+                Layout::Random { density: 45 },
+                seed,
+                true,
+            );
+
+            assert_eq!(1, connected_components(&board).len(), "seed {}", seed);
+            assert!(!board.obstacle_locations.contains(&Vector::new(3, 3)));
+        }
+    }
+}
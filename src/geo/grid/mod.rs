@@ -0,0 +1,354 @@
+use std::convert::TryFrom;
+
+use crate::geo::Direction::*;
+use crate::geo::RelativeDirection::*;
+use crate::geo::{Compass, Direction, RelativeDirection, Square, Vector};
+
+/// The shape of the cells on a board, and so how a robot moves and turns between them.
+pub trait Grid {
+    /// The adjacent cell in `direction`, or `None` if it would lie past the limits of `i16`.
+    fn neighbour(&self, location: &Vector, direction: Direction) -> Option<Vector>;
+
+    fn rotate(&self, direction: &Direction, relative_direction: &RelativeDirection) -> Direction;
+
+    fn supports(&self, direction: &Direction) -> bool;
+
+    fn contains(&self, bounds: &Square, location: &Vector) -> bool;
+
+    /// Cells beside a move that must be free for the robot not to cut a corner.
+    fn corners(&self, location: &Vector, direction: Direction) -> Vec<Vector>;
+
+    /// The cells on the lowest level of `bounds` laid out as lines of text, top line first, with
+    /// `None` for gaps.
+    fn layout(&self, bounds: &Square) -> Vec<Vec<Option<Vector>>>;
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct SquareGrid {
+    pub compass: Compass,
+}
+
+impl Grid for SquareGrid {
+    fn neighbour(&self, location: &Vector, direction: Direction) -> Option<Vector> {
+        location.translate(direction)
+    }
+
+    fn rotate(&self, direction: &Direction, relative_direction: &RelativeDirection) -> Direction {
+        self.compass.rotate(direction, relative_direction)
+    }
+
+    fn supports(&self, direction: &Direction) -> bool {
+        self.compass.supports(direction)
+    }
+
+    fn contains(&self, bounds: &Square, location: &Vector) -> bool {
+        bounds.contains(location)
+    }
+
+    fn corners(&self, location: &Vector, direction: Direction) -> Vec<Vector> {
+        direction
+            .components()
+            .map_or_else(Vec::new, |(vertical, horizontal)| {
+                [vertical, horizontal]
+                    .iter()
+                    .filter_map(|component| location.translate(*component))
+                    .collect()
+            })
+    }
+
+    fn layout(&self, bounds: &Square) -> Vec<Vec<Option<Vector>>> {
+        ((bounds.bottom_left.y)..=(bounds.top_right.y))
+            .rev()
+            .map(|y| {
+                ((bounds.bottom_left.x)..=(bounds.top_right.x))
+                    .map(|x| Some(Vector::at_level(x, y, bounds.bottom_left.z)))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Flat-topped hexagons in axial coordinates.
+///
+/// `x` counts columns eastwards and `y` runs north, so `NorthEast` is `(+1, +1)` and `SouthEast`
+/// is `(+1, 0)`. Bounds are given in columns and rows, with each odd column sitting half a row
+/// below its neighbours, so that a board is rectangular rather than a parallelogram.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct HexGrid;
+
+impl HexGrid {
+    /// Widened so that a location far off the board still has a row rather than overflowing.
+    fn row(location: &Vector) -> i32 {
+        i32::from(location.y) - i32::from(location.x.div_euclid(2))
+    }
+}
+
+impl Grid for HexGrid {
+    fn neighbour(&self, location: &Vector, direction: Direction) -> Option<Vector> {
+        let (dx, dy) = match direction {
+            North => (0, 1),
+            South => (0, -1),
+            NorthEast => (1, 1),
+            SouthWest => (-1, -1),
+            SouthEast => (1, 0),
+            NorthWest => (-1, 0),
+            East | West => return None,
+        };
+
+        Some(Vector::at_level(
+            location.x.checked_add(dx)?,
+            location.y.checked_add(dy)?,
+            location.z,
+        ))
+    }
+
+    fn rotate(&self, direction: &Direction, relative_direction: &RelativeDirection) -> Direction {
+        match (direction, relative_direction) {
+            (North, Right) => NorthEast,
+            (NorthEast, Right) => SouthEast,
+            (SouthEast, Right) => South,
+            (South, Right) => SouthWest,
+            (SouthWest, Right) => NorthWest,
+            (NorthWest, Right) => North,
+            (North, Left) => NorthWest,
+            (NorthWest, Left) => SouthWest,
+            (SouthWest, Left) => South,
+            (South, Left) => SouthEast,
+            (SouthEast, Left) => NorthEast,
+            (NorthEast, Left) => North,
+            (East, _) | (West, _) => *direction,
+        }
+    }
+
+    fn supports(&self, direction: &Direction) -> bool {
+        !matches!(direction, East | West)
+    }
+
+    fn contains(&self, bounds: &Square, location: &Vector) -> bool {
+        let row = HexGrid::row(location);
+
+        location.x >= bounds.bottom_left.x
+            && location.x <= bounds.top_right.x
+            && row >= i32::from(bounds.bottom_left.y)
+            && row <= i32::from(bounds.top_right.y)
+            && bounds.contains_level(location.z)
+    }
+
+    fn corners(&self, _location: &Vector, _direction: Direction) -> Vec<Vector> {
+        Vec::new()
+    }
+
+    fn layout(&self, bounds: &Square) -> Vec<Vec<Option<Vector>>> {
+        // Each line of text is half a row, so a cell at (x, y) sits on line 2y - x. Lines are
+        // counted in i32 as there are twice as many of them as rows.
+        let top_line = 2 * i32::from(bounds.top_right.y);
+        let bottom_line = 2 * i32::from(bounds.bottom_left.y) - 1;
+
+        (bottom_line..=top_line)
+            .rev()
+            .map(|line| {
+                ((bounds.bottom_left.x)..=(bounds.top_right.x))
+                    .map(|x| {
+                        let doubled_y = line + i32::from(x);
+
+                        if doubled_y.rem_euclid(2) == 0 {
+                            i16::try_from(doubled_y / 2)
+                                .ok()
+                                .map(|y| Vector::at_level(x, y, bounds.bottom_left.z))
+                                .filter(|location| self.contains(bounds, location))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<Option<Vector>>>()
+            })
+            .filter(|line| line.iter().any(Option::is_some))
+            .collect()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum GridShape {
+    Square(SquareGrid),
+    Hex(HexGrid),
+}
+
+impl GridShape {
+    fn grid(&self) -> &dyn Grid {
+        match self {
+            GridShape::Square(grid) => grid,
+            GridShape::Hex(grid) => grid,
+        }
+    }
+}
+
+impl Grid for GridShape {
+    fn neighbour(&self, location: &Vector, direction: Direction) -> Option<Vector> {
+        self.grid().neighbour(location, direction)
+    }
+
+    fn rotate(&self, direction: &Direction, relative_direction: &RelativeDirection) -> Direction {
+        self.grid().rotate(direction, relative_direction)
+    }
+
+    fn supports(&self, direction: &Direction) -> bool {
+        self.grid().supports(direction)
+    }
+
+    fn contains(&self, bounds: &Square, location: &Vector) -> bool {
+        self.grid().contains(bounds, location)
+    }
+
+    fn corners(&self, location: &Vector, direction: Direction) -> Vec<Vector> {
+        self.grid().corners(location, direction)
+    }
+
+    fn layout(&self, bounds: &Square) -> Vec<Vec<Option<Vector>>> {
+        self.grid().layout(bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    mod square_grid {
+        use crate::geo::grid::{Grid, SquareGrid};
+        use crate::geo::Compass::*;
+        use crate::geo::Direction::*;
+        use crate::geo::{Square, Vector};
+
+        #[test]
+        fn corners_for_diagonal_move() {
+            let grid = SquareGrid { compass: EightWay };
+
+            assert_eq!(
+                vec![Vector::new(2, 3), Vector::new(3, 2)],
+                grid.corners(&Vector::new(2, 2), NorthEast)
+            )
+        }
+
+        #[test]
+        fn no_corners_for_orthogonal_move() {
+            let grid = SquareGrid { compass: EightWay };
+
+            assert_eq!(
+                Vec::<Vector>::new(),
+                grid.corners(&Vector::new(2, 2), North)
+            )
+        }
+
+        #[test]
+        fn layout_is_rows_from_the_top() {
+            let grid = SquareGrid { compass: FourWay };
+            let bounds = Square::with_corners(&Vector::new(0, 0), &Vector::new(1, 1));
+
+            assert_eq!(
+                vec![
+                    vec![Some(Vector::new(0, 1)), Some(Vector::new(1, 1))],
+                    vec![Some(Vector::new(0, 0)), Some(Vector::new(1, 0))],
+                ],
+                grid.layout(&bounds)
+            )
+        }
+    }
+
+    mod hex_grid {
+        use crate::geo::grid::{Grid, HexGrid};
+        use crate::geo::Direction::*;
+        use crate::geo::RelativeDirection::*;
+        use crate::geo::{Square, Vector};
+
+        #[test]
+        fn neighbour_north_east() {
+            assert_eq!(
+                Some(Vector::new(3, 3)),
+                HexGrid.neighbour(&Vector::new(2, 2), NorthEast)
+            )
+        }
+
+        #[test]
+        fn neighbour_south_east() {
+            assert_eq!(
+                Some(Vector::new(3, 2)),
+                HexGrid.neighbour(&Vector::new(2, 2), SouthEast)
+            )
+        }
+
+        #[test]
+        fn neighbour_north_west() {
+            assert_eq!(
+                Some(Vector::new(1, 2)),
+                HexGrid.neighbour(&Vector::new(2, 2), NorthWest)
+            )
+        }
+
+        #[test]
+        fn rotate_right_is_a_sixth_turn() {
+            assert_eq!(SouthEast, HexGrid.rotate(&NorthEast, &Right))
+        }
+
+        #[test]
+        fn rotate_left_is_a_sixth_turn() {
+            assert_eq!(SouthWest, HexGrid.rotate(&NorthWest, &Left))
+        }
+
+        #[test]
+        fn six_rotations_are_identity() {
+            let rotated = (0..6).fold(North, |facing, _| HexGrid.rotate(&facing, &Right));
+
+            assert_eq!(North, rotated)
+        }
+
+        #[test]
+        fn does_not_support_east() {
+            assert!(!HexGrid.supports(&East))
+        }
+
+        #[test]
+        fn contains_shifts_odd_columns() {
+            let bounds = Square::with_corners(&Vector::new(0, 0), &Vector::new(4, 4));
+
+            assert!(HexGrid.contains(&bounds, &Vector::new(4, 6)));
+            assert!(!HexGrid.contains(&bounds, &Vector::new(4, 1)));
+            assert!(HexGrid.contains(&bounds, &Vector::new(1, 0)));
+            assert!(!HexGrid.contains(&bounds, &Vector::new(1, 5)));
+        }
+
+        #[test]
+        fn no_neighbour_east_or_west() {
+            assert_eq!(None, HexGrid.neighbour(&Vector::new(1, 1), East));
+            assert_eq!(None, HexGrid.neighbour(&Vector::new(1, 1), West));
+        }
+
+        #[test]
+        fn neighbour_past_the_limits() {
+            assert_eq!(
+                None,
+                HexGrid.neighbour(&Vector::new(i16::MAX, 0), NorthEast)
+            );
+            assert_eq!(None, HexGrid.neighbour(&Vector::new(0, i16::MIN), South));
+        }
+
+        // Found by fuzzing: working out the row of a cell this far south overflowed.
+        #[test]
+        fn contains_is_false_far_off_the_board() {
+            let bounds = Square::with_corners(&Vector::new(0, 0), &Vector::new(4, 4));
+
+            assert!(!HexGrid.contains(&bounds, &Vector::new(5, i16::MIN)));
+            assert!(!HexGrid.contains(&bounds, &Vector::new(i16::MIN, i16::MAX)));
+        }
+
+        #[test]
+        fn layout_staggers_odd_columns() {
+            let bounds = Square::with_corners(&Vector::new(0, 0), &Vector::new(2, 0));
+
+            assert_eq!(
+                vec![
+                    vec![Some(Vector::new(0, 0)), None, Some(Vector::new(2, 1))],
+                    vec![None, Some(Vector::new(1, 0)), None],
+                ],
+                HexGrid.layout(&bounds)
+            )
+        }
+    }
+}
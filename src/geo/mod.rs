@@ -3,20 +3,53 @@ use std::fmt::{Error, Formatter};
 use Direction::*;
 use RelativeDirection::*;
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub mod grid;
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum Direction {
     North,
     South,
     East,
     West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum RelativeDirection {
     Left,
     Right,
 }
 
+/// The set of directions a robot may face, and so how far a single rotation turns it.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Compass {
+    FourWay,
+    EightWay,
+}
+
+impl Compass {
+    pub fn rotate(
+        &self,
+        direction: &Direction,
+        relative_direction: &RelativeDirection,
+    ) -> Direction {
+        match self {
+            Compass::FourWay => direction.rotate(relative_direction),
+            Compass::EightWay => direction.rotate_eighth(relative_direction),
+        }
+    }
+
+    pub fn supports(&self, direction: &Direction) -> bool {
+        match self {
+            Compass::FourWay => !direction.is_diagonal(),
+            Compass::EightWay => true,
+        }
+    }
+}
+
 impl Direction {
     pub fn rotate(&self, relative_direction: &RelativeDirection) -> Direction {
         match (self, relative_direction) {
@@ -28,6 +61,50 @@ impl Direction {
             (East, Right) => South,
             (South, Right) => West,
             (West, Right) => North,
+            (NorthEast, Left) => NorthWest,
+            (NorthWest, Left) => SouthWest,
+            (SouthWest, Left) => SouthEast,
+            (SouthEast, Left) => NorthEast,
+            (NorthEast, Right) => SouthEast,
+            (SouthEast, Right) => SouthWest,
+            (SouthWest, Right) => NorthWest,
+            (NorthWest, Right) => NorthEast,
+        }
+    }
+
+    pub fn rotate_eighth(&self, relative_direction: &RelativeDirection) -> Direction {
+        match (self, relative_direction) {
+            (North, Left) => NorthWest,
+            (NorthWest, Left) => West,
+            (West, Left) => SouthWest,
+            (SouthWest, Left) => South,
+            (South, Left) => SouthEast,
+            (SouthEast, Left) => East,
+            (East, Left) => NorthEast,
+            (NorthEast, Left) => North,
+            (North, Right) => NorthEast,
+            (NorthEast, Right) => East,
+            (East, Right) => SouthEast,
+            (SouthEast, Right) => South,
+            (South, Right) => SouthWest,
+            (SouthWest, Right) => West,
+            (West, Right) => NorthWest,
+            (NorthWest, Right) => North,
+        }
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        self.components().is_some()
+    }
+
+    /// The two cardinal directions that make up a diagonal direction, north/south first.
+    pub fn components(&self) -> Option<(Direction, Direction)> {
+        match self {
+            NorthEast => Some((North, East)),
+            NorthWest => Some((North, West)),
+            SouthEast => Some((South, East)),
+            SouthWest => Some((South, West)),
+            North | South | East | West => None,
         }
     }
 }
@@ -42,6 +119,10 @@ impl std::fmt::Display for Direction {
                 South => "South",
                 East => "East",
                 West => "West",
+                NorthEast => "NorthEast",
+                NorthWest => "NorthWest",
+                SouthEast => "SouthEast",
+                SouthWest => "SouthWest",
             }
         )
     }
@@ -51,31 +132,46 @@ impl std::fmt::Display for Direction {
 pub struct Vector {
     pub x: i16,
     pub y: i16,
+    /// The level of a stacked board this location is on, where 0 is the ground level.
+    pub z: i16,
 }
 
 impl Vector {
     pub fn new(x: i16, y: i16) -> Vector {
-        Vector { x, y }
+        Vector { x, y, z: 0 }
+    }
+
+    pub fn at_level(x: i16, y: i16, z: i16) -> Vector {
+        Vector { x, y, z }
     }
 
-    pub fn translate(&self, direction: Direction) -> Vector {
+    pub fn with_level(&self, z: i16) -> Vector {
+        Vector { z, ..(*self) }
+    }
+
+    /// The next location in `direction`, or `None` if that would take a coordinate past the
+    /// limits of `i16`.
+    pub fn translate(&self, direction: Direction) -> Option<Vector> {
         match direction {
-            North => Vector {
-                y: self.y + 1,
-                ..(*self)
-            },
-            South => Vector {
-                y: self.y - 1,
-                ..(*self)
-            },
-            East => Vector {
-                x: self.x + 1,
-                ..(*self)
-            },
-            West => Vector {
-                x: self.x - 1,
-                ..(*self)
-            },
+            North => self.y.checked_add(1).map(|y| Vector { y, ..(*self) }),
+            South => self.y.checked_sub(1).map(|y| Vector { y, ..(*self) }),
+            East => self.x.checked_add(1).map(|x| Vector { x, ..(*self) }),
+            West => self.x.checked_sub(1).map(|x| Vector { x, ..(*self) }),
+            NorthEast | NorthWest | SouthEast | SouthWest => {
+                let (vertical, horizontal) = direction.components().unwrap();
+
+                self.translate(vertical)?.translate(horizontal)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Vector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        if self.z == 0 {
+            write!(f, "{},{}", self.x, self.y)
+        } else {
+            write!(f, "{},{},{}", self.x, self.y, self.z)
         }
     }
 }
@@ -94,10 +190,12 @@ impl Square {
         let x_max = max(corner1.x, corner2.x);
         let y_min = min(corner1.y, corner2.y);
         let y_max = max(corner1.y, corner2.y);
+        let z_min = min(corner1.z, corner2.z);
+        let z_max = max(corner1.z, corner2.z);
 
         Square {
-            bottom_left: Vector::new(x_min, y_min),
-            top_right: Vector::new(x_max, y_max),
+            bottom_left: Vector::at_level(x_min, y_min, z_min),
+            top_right: Vector::at_level(x_max, y_max, z_max),
         }
     }
 
@@ -106,6 +204,15 @@ impl Square {
             && vector.x <= self.top_right.x
             && vector.y >= self.bottom_left.y
             && vector.y <= self.top_right.y
+            && self.contains_level(vector.z)
+    }
+
+    pub fn contains_level(self: Square, z: i16) -> bool {
+        z >= self.bottom_left.z && z <= self.top_right.z
+    }
+
+    pub fn is_stacked(self: Square) -> bool {
+        self.bottom_left.z != self.top_right.z
     }
 }
 
@@ -155,6 +262,67 @@ mod tests {
         fn rotate_west_right_gives_north() {
             assert_eq!(West.rotate(&Right), North)
         }
+
+        #[test]
+        fn rotate_north_east_left_gives_north_west() {
+            assert_eq!(NorthEast.rotate(&Left), NorthWest)
+        }
+
+        #[test]
+        fn rotate_south_west_right_gives_north_west() {
+            assert_eq!(SouthWest.rotate(&Right), NorthWest)
+        }
+    }
+
+    mod rotate_eighth {
+        use crate::geo::Direction::*;
+        use crate::geo::RelativeDirection::*;
+
+        #[test]
+        fn rotate_eighth_north_left_gives_north_west() {
+            assert_eq!(North.rotate_eighth(&Left), NorthWest)
+        }
+
+        #[test]
+        fn rotate_eighth_north_west_left_gives_west() {
+            assert_eq!(NorthWest.rotate_eighth(&Left), West)
+        }
+
+        #[test]
+        fn rotate_eighth_north_right_gives_north_east() {
+            assert_eq!(North.rotate_eighth(&Right), NorthEast)
+        }
+
+        #[test]
+        fn rotate_eighth_south_east_right_gives_south() {
+            assert_eq!(SouthEast.rotate_eighth(&Right), South)
+        }
+    }
+
+    mod compass {
+        use crate::geo::Compass::*;
+        use crate::geo::Direction::*;
+        use crate::geo::RelativeDirection::*;
+
+        #[test]
+        fn four_way_rotates_a_quarter_turn() {
+            assert_eq!(FourWay.rotate(&North, &Right), East)
+        }
+
+        #[test]
+        fn eight_way_rotates_an_eighth_turn() {
+            assert_eq!(EightWay.rotate(&North, &Right), NorthEast)
+        }
+
+        #[test]
+        fn four_way_does_not_support_diagonals() {
+            assert!(!FourWay.supports(&SouthWest))
+        }
+
+        #[test]
+        fn eight_way_supports_diagonals() {
+            assert!(EightWay.supports(&SouthWest))
+        }
     }
 
     mod translate {
@@ -163,22 +331,68 @@ mod tests {
 
         #[test]
         fn translate_north() {
-            assert_eq!(Vector::new(2, 2).translate(North), Vector::new(2, 3))
+            assert_eq!(Vector::new(2, 2).translate(North), Some(Vector::new(2, 3)))
         }
 
         #[test]
         fn translate_east() {
-            assert_eq!(Vector::new(2, 2).translate(East), Vector::new(3, 2))
+            assert_eq!(Vector::new(2, 2).translate(East), Some(Vector::new(3, 2)))
         }
 
         #[test]
         fn translate_south() {
-            assert_eq!(Vector::new(2, 2).translate(South), Vector::new(2, 1))
+            assert_eq!(Vector::new(2, 2).translate(South), Some(Vector::new(2, 1)))
         }
 
         #[test]
         fn translate_west() {
-            assert_eq!(Vector::new(2, 2).translate(West), Vector::new(1, 2))
+            assert_eq!(Vector::new(2, 2).translate(West), Some(Vector::new(1, 2)))
+        }
+
+        #[test]
+        fn translate_keeps_level() {
+            assert_eq!(
+                Vector::at_level(2, 2, 3).translate(North),
+                Some(Vector::at_level(2, 3, 3))
+            )
+        }
+
+        #[test]
+        fn translate_past_the_limits() {
+            assert_eq!(Vector::new(0, i16::MAX).translate(North), None);
+            assert_eq!(Vector::new(0, i16::MIN).translate(South), None);
+            assert_eq!(Vector::new(i16::MAX, 0).translate(East), None);
+            assert_eq!(Vector::new(i16::MIN, 0).translate(West), None);
+        }
+
+        #[test]
+        fn translate_diagonally_past_one_limit() {
+            assert_eq!(Vector::new(i16::MAX, 0).translate(NorthEast), None);
+            assert_eq!(Vector::new(0, i16::MIN).translate(SouthEast), None);
+        }
+
+        #[test]
+        fn translate_up_to_the_limit() {
+            assert_eq!(
+                Vector::new(i16::MIN, i16::MAX - 1).translate(North),
+                Some(Vector::new(i16::MIN, i16::MAX))
+            )
+        }
+
+        #[test]
+        fn translate_north_east() {
+            assert_eq!(
+                Vector::new(2, 2).translate(NorthEast),
+                Some(Vector::new(3, 3))
+            )
+        }
+
+        #[test]
+        fn translate_south_west() {
+            assert_eq!(
+                Vector::new(2, 2).translate(SouthWest),
+                Some(Vector::new(1, 1))
+            )
         }
     }
 
@@ -204,6 +418,25 @@ mod tests {
         fn display_west() {
             assert_eq!("West", format!("{}", West))
         }
+
+        #[test]
+        fn display_north_east() {
+            assert_eq!("NorthEast", format!("{}", NorthEast))
+        }
+    }
+
+    mod vector_display {
+        use crate::geo::Vector;
+
+        #[test]
+        fn display_vector() {
+            assert_eq!("2,-3", format!("{}", Vector::new(2, -3)))
+        }
+
+        #[test]
+        fn display_vector_above_ground_level() {
+            assert_eq!("2,3,1", format!("{}", Vector::at_level(2, 3, 1)))
+        }
     }
 
     mod square_contains {
@@ -215,7 +448,7 @@ mod tests {
             let square = Square::with_corners(&Vector::new(0, 0), &Vector::new(4, 4));
             let vector = Vector::new(1, 1);
 
-            assert_eq!(true, square.contains(&vector));
+            assert!(square.contains(&vector));
         }
 
         #[test]
@@ -223,7 +456,7 @@ mod tests {
             let square = Square::with_corners(&Vector::new(0, 0), &Vector::new(4, 4));
             let vector = Vector::new(1, 5);
 
-            assert_eq!(false, square.contains(&vector));
+            assert!(!square.contains(&vector));
         }
 
         #[test]
@@ -231,7 +464,7 @@ mod tests {
             let square = Square::with_corners(&Vector::new(0, 0), &Vector::new(4, 4));
             let vector = Vector::new(1, -1);
 
-            assert_eq!(false, square.contains(&vector));
+            assert!(!square.contains(&vector));
         }
 
         #[test]
@@ -239,7 +472,7 @@ mod tests {
             let square = Square::with_corners(&Vector::new(0, 0), &Vector::new(4, 4));
             let vector = Vector::new(5, 1);
 
-            assert_eq!(false, square.contains(&vector));
+            assert!(!square.contains(&vector));
         }
 
         #[test]
@@ -247,7 +480,189 @@ mod tests {
             let square = Square::with_corners(&Vector::new(0, 0), &Vector::new(4, 4));
             let vector = Vector::new(-1, 1);
 
-            assert_eq!(false, square.contains(&vector));
+            assert!(!square.contains(&vector));
+        }
+
+        #[test]
+        fn contains_on_upper_level() {
+            let square = Square::with_corners(&Vector::new(0, 0), &Vector::at_level(4, 4, 2));
+            let vector = Vector::at_level(1, 1, 2);
+
+            assert!(square.contains(&vector));
+        }
+
+        #[test]
+        fn contains_out_of_bounds_above() {
+            let square = Square::with_corners(&Vector::new(0, 0), &Vector::at_level(4, 4, 2));
+            let vector = Vector::at_level(1, 1, 3);
+
+            assert!(!square.contains(&vector));
+        }
+    }
+
+    /// Turning and translating checked for random directions and vectors, each from a seed the
+    /// assertion names.
+    mod properties {
+        use crate::geo::grid::{GridShape, SquareGrid};
+        use crate::geo::Direction::*;
+        use crate::geo::RelativeDirection::*;
+        use crate::geo::{Compass, Direction, RelativeDirection, Vector};
+        use crate::random::SeededRandom;
+        use crate::test_support::{any_compass, any_direction, turns_in_full_circle};
+
+        const CASES: u64 = 500;
+
+        fn any_turns(random: &mut SeededRandom) -> Vec<RelativeDirection> {
+            (0..random.below(20))
+                .map(|_| [Left, Right][random.below(2)])
+                .collect()
+        }
+
+        fn any_vector(random: &mut SeededRandom) -> Vector {
+            let mut coordinate = || random.below(200) as i16 - 100;
+
+            Vector::at_level(coordinate(), coordinate(), coordinate())
+        }
+
+        fn turned(compass: Compass, facing: Direction, turns: &[RelativeDirection]) -> Direction {
+            turns
+                .iter()
+                .fold(facing, |facing, turn| compass.rotate(&facing, turn))
+        }
+
+        fn square(compass: Compass) -> GridShape {
+            GridShape::Square(SquareGrid { compass })
+        }
+
+        #[test]
+        fn a_full_circle_of_lefts_or_rights_is_identity() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let compass = any_compass(&mut random);
+                let facing = any_direction(&mut random);
+                let turn = [Left, Right][random.below(2)];
+                let circle = vec![turn; turns_in_full_circle(&square(compass))];
+
+                assert_eq!(facing, turned(compass, facing, &circle), "seed {}", seed);
+            }
+        }
+
+        #[test]
+        fn left_then_right_is_identity() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let compass = any_compass(&mut random);
+                let facing = any_direction(&mut random);
+
+                assert_eq!(
+                    facing,
+                    turned(compass, facing, &[Left, Right]),
+                    "seed {}",
+                    seed
+                );
+                assert_eq!(
+                    facing,
+                    turned(compass, facing, &[Right, Left]),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+
+        #[test]
+        fn turning_depends_only_on_lefts_less_rights() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let compass = any_compass(&mut random);
+                let facing = any_direction(&mut random);
+                let turns = any_turns(&mut random);
+
+                let circle = turns_in_full_circle(&square(compass)) as i32;
+                let lefts = turns.iter().filter(|turn| **turn == Left).count() as i32;
+                let net_lefts = (lefts - (turns.len() as i32 - lefts)).rem_euclid(circle);
+
+                assert_eq!(
+                    turned(compass, facing, &vec![Left; net_lefts as usize]),
+                    turned(compass, facing, &turns),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+
+        #[test]
+        fn translating_there_and_back_is_identity() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let vector = any_vector(&mut random);
+                let direction = any_direction(&mut random);
+                let opposite = direction.rotate(&Left).rotate(&Left);
+
+                assert_eq!(
+                    Some(vector),
+                    vector
+                        .translate(direction)
+                        .and_then(|moved| moved.translate(opposite)),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+
+        #[test]
+        fn translating_fails_only_past_the_limits() {
+            let limits = [i16::MIN, i16::MIN + 1, -1, 0, 1, i16::MAX - 1, i16::MAX];
+
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let vector = Vector::new(
+                    limits[random.below(limits.len())],
+                    limits[random.below(limits.len())],
+                );
+                let direction = any_direction(&mut random);
+
+                let wide_x = i32::from(vector.x) + unit_step(direction).0;
+                let wide_y = i32::from(vector.y) + unit_step(direction).1;
+                let in_range =
+                    |wide: i32| i32::from(i16::MIN) <= wide && wide <= i32::from(i16::MAX);
+
+                assert_eq!(
+                    in_range(wide_x) && in_range(wide_y),
+                    vector.translate(direction).is_some(),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+
+        fn unit_step(direction: Direction) -> (i32, i32) {
+            match direction {
+                North => (0, 1),
+                South => (0, -1),
+                East => (1, 0),
+                West => (-1, 0),
+                NorthEast => (1, 1),
+                NorthWest => (-1, 1),
+                SouthEast => (1, -1),
+                SouthWest => (-1, -1),
+            }
+        }
+
+        #[test]
+        fn translating_stays_on_the_same_level() {
+            for seed in 0..CASES {
+                let mut random = SeededRandom::new(seed);
+                let vector = any_vector(&mut random);
+
+                assert_eq!(
+                    Some(vector.z),
+                    vector
+                        .translate(any_direction(&mut random))
+                        .map(|moved| moved.z),
+                    "seed {}",
+                    seed
+                );
+            }
         }
     }
 }
@@ -0,0 +1,18 @@
+pub mod analysis;
+pub mod commands;
+pub mod debugger;
+pub mod error;
+pub mod fleet;
+pub mod game_execution;
+pub mod game_model;
+pub mod generation;
+pub mod geo;
+pub mod map_format;
+pub mod planning;
+pub mod puzzle;
+pub mod random;
+pub mod scenario;
+pub mod session;
+
+#[cfg(test)]
+mod test_support;
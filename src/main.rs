@@ -1,38 +1,324 @@
-use std::io;
 use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::{fs, io, process};
 
-use crate::commands::parsing::parse_command;
-use crate::game_execution::{is_board_valid, output_from_command, update_board_from_command};
-use crate::game_model::Board;
-use crate::geo::Vector;
-
-mod commands;
-mod game_execution;
-mod game_model;
-mod geo;
+use rust_toy_robot::debugger::{parse_debug_command, DebugCommand, Debugger};
+use rust_toy_robot::error::{ConfigError, RobotError};
+use rust_toy_robot::game_execution::simulator::Simulator;
+use rust_toy_robot::game_model::{Board, EnergyModel};
+use rust_toy_robot::geo::grid::{GridShape, HexGrid, SquareGrid};
+use rust_toy_robot::geo::{Compass, Vector};
+use rust_toy_robot::puzzle::{builtin_levels, level_text, parse_level, Level};
+use rust_toy_robot::scenario::run_scenario;
+use rust_toy_robot::session::{args_line, parse_recording, replay};
 
 fn main() {
+    if let Err(error) = run(std::env::args().skip(1).collect()) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), RobotError> {
     let stdin = io::stdin();
 
-    let mut board = Board::empty_with_corner(&Vector::new(4, 4));
+    if let Some(path) = flag_value::<String>(&args, "--replay")? {
+        return replay_recording(&path);
+    }
+
+    if args.first().map(String::as_str) == Some("test") {
+        return run_scenarios(&args[1..]);
+    }
+
+    if args.first().map(String::as_str) == Some("debug") {
+        let path = args
+            .get(1)
+            .ok_or_else(|| ConfigError::MissingFlagValue("debug".to_string()))?;
+
+        return debug_script(path, &args[2..]);
+    }
+
+    let (board, level) = initial_board(&args)?;
+    let mut simulator = Simulator::new(&board);
+
+    if let Some(level) = level.as_ref().filter(|level| !level.name.is_empty()) {
+        println!("Level: {}", level.name);
+    }
+
+    let mut recording = match flag_value::<String>(&args, "--record")? {
+        Some(path) => {
+            let mut file =
+                fs::File::create(&path).map_err(|io_error| RobotError::io(&path, io_error))?;
+            writeln!(file, "{}", args_line(&without_flag(&args, "--record")))
+                .map_err(|io_error| RobotError::io(&path, io_error))?;
+            Some((path, file))
+        }
+        None => None,
+    };
+
+    let mut commands_used: usize = 0;
 
     for line in stdin.lock().lines() {
-        let raw_user_input = line.unwrap();
+        let raw_user_input = line.map_err(|io_error| RobotError::io("stdin", io_error))?;
+
+        let (entry, outcome) = simulator.run_line(raw_user_input.as_str());
+
+        if let Some((path, file)) = &mut recording {
+            write!(file, "{}", entry).map_err(|io_error| RobotError::io(path, io_error))?;
+        }
+
+        match outcome {
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => {}
+            Err(parse_error @ RobotError::Parse { .. }) => {
+                eprintln!("{}", parse_error);
+                continue;
+            }
+            Err(execution_error) => eprintln!("{}", execution_error),
+        }
+
+        commands_used += 1;
+
+        if let Some(level) = &level {
+            if level.is_complete(simulator.board()) {
+                println!(
+                    "Goal reached in {} commands. Score: {}",
+                    commands_used,
+                    level.score(commands_used)
+                );
+                break;
+            } else if level.is_out_of_commands(commands_used) {
+                println!("Out of commands after {}", commands_used);
+                break;
+            }
+        }
+    }
+
+    if let Some(level) = &level {
+        if !level.is_complete(simulator.board()) && !level.is_out_of_commands(commands_used) {
+            println!("Level unfinished after {} commands", commands_used);
+        }
+    }
+
+    if let Some(path) = flag_value::<String>(&args, "--save")? {
+        save_board(&path, simulator.board(), level.as_ref())?;
+    }
+
+    Ok(())
+}
+
+/// Writes the board as a level file that `--level` loads again, keeping the level's name, budget
+/// and required items if there is one.
+fn save_board(path: &str, board: &Board, level: Option<&Level>) -> Result<(), RobotError> {
+    let saved = match level {
+        Some(level) => Level {
+            board: board.clone(),
+            ..level.clone()
+        },
+        None => Level {
+            name: String::new(),
+            board: board.clone(),
+            required_items: Vec::new(),
+            command_budget: None,
+        },
+    };
+
+    let text = level_text(&saved).map_err(ConfigError::from)?;
+
+    fs::write(path, text).map_err(|io_error| RobotError::io(path, io_error))
+}
+
+/// The board the program starts with, from the grid, level, and battery flags.
+fn initial_board(args: &[String]) -> Result<(Board, Option<Level>), RobotError> {
+    let grid = if args.iter().any(|arg| arg == "--hex") {
+        GridShape::Hex(HexGrid)
+    } else if args.iter().any(|arg| arg == "--eight-way") {
+        GridShape::Square(SquareGrid {
+            compass: Compass::EightWay,
+        })
+    } else {
+        GridShape::Square(SquareGrid {
+            compass: Compass::FourWay,
+        })
+    };
+
+    let top_level = match flag_value::<i16>(args, "--levels")? {
+        Some(levels) if levels < 1 => {
+            return Err(ConfigError::BadFlagValue {
+                flag: "--levels".to_string(),
+                value: levels.to_string(),
+            }
+            .into())
+        }
+        Some(levels) => levels - 1,
+        None => 0,
+    };
+
+    let mut board = Board::empty_with_corner(&Vector::at_level(4, 4, top_level)).with_grid(grid);
+
+    if let Some(capacity) = flag_value::<u32>(args, "--battery")? {
+        board = board.with_energy_model(EnergyModel {
+            capacity,
+            move_cost: flag_value(args, "--move-cost")?.unwrap_or(1),
+            rotate_cost: flag_value(args, "--rotate-cost")?.unwrap_or(1),
+            carpet_cost: flag_value(args, "--carpet-cost")?
+                .unwrap_or(EnergyModel::DEFAULT_CARPET_COST),
+        });
+    }
+
+    let level = load_level(args)?;
 
-        let command_or_error = parse_command(raw_user_input.as_str());
+    if let Some(level) = &level {
+        board = level.board.clone();
+    }
+
+    Ok((board, level))
+}
+
+/// Runs a `--record` file again with the flags it was recorded with, and fails at the first input
+/// that no longer does what it did.
+fn replay_recording(path: &str) -> Result<(), RobotError> {
+    let text = fs::read_to_string(path).map_err(|io_error| RobotError::io(path, io_error))?;
+    let (args, entries) = parse_recording(&text).map_err(ConfigError::from)?;
+    let (board, _) = initial_board(&args)?;
+
+    let count = replay(&board, &entries)?;
+    println!("Replayed {} inputs with no divergence", count);
 
-        command_or_error
-            .map(|command| {
-                output_from_command(&board, &command).map(|output| {
-                    println!("{}", output);
-                });
+    Ok(())
+}
+
+/// The puzzle level chosen by `--level <file>` or `--builtin-level <number>`, if any.
+fn load_level(args: &[String]) -> Result<Option<Level>, RobotError> {
+    if let Some(number) = flag_value::<usize>(args, "--builtin-level")? {
+        return match builtin_levels().into_iter().nth(number.wrapping_sub(1)) {
+            Some(level) => Ok(Some(level)),
+            None => Err(ConfigError::NoSuchBuiltinLevel(number).into()),
+        };
+    }
 
-                let new_board = update_board_from_command(&board, &command);
+    match flag_value::<String>(args, "--level")? {
+        Some(path) => {
+            let text =
+                fs::read_to_string(&path).map_err(|io_error| RobotError::io(&path, io_error))?;
+
+            Ok(Some(parse_level(&text).map_err(ConfigError::from)?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Steps through a script with debug commands read from stdin.
+fn debug_script(path: &str, args: &[String]) -> Result<(), RobotError> {
+    let script = fs::read_to_string(path).map_err(|io_error| RobotError::io(path, io_error))?;
+
+    let (board, _) = initial_board(args)?;
+    let mut debugger = Debugger::new(&board, &script);
+
+    println!("{}", debugger.execute(&DebugCommand::Show).unwrap());
+
+    for line in io::stdin().lock().lines() {
+        let line = line.map_err(|io_error| RobotError::io("stdin", io_error))?;
+
+        match parse_debug_command(line.as_str()) {
+            Ok(DebugCommand::Quit) => return Ok(()),
+            Ok(command) => match debugger.execute(&command) {
+                Ok(output) => println!("{}", output),
+                Err(debug_error) => eprintln!("{}", debug_error),
+            },
+            Err(debug_error) => eprintln!("{}", debug_error),
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs every `.robot` scenario named in the args, or found in a directory named in them,
+/// printing PASS or FAIL for each and failing if any did.
+fn run_scenarios(args: &[String]) -> Result<(), RobotError> {
+    let (board, _) = initial_board(args)?;
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+
+    for arg in args {
+        let path = Path::new(arg);
+
+        if path.is_dir() {
+            let mut found: Vec<PathBuf> = fs::read_dir(path)
+                .map_err(|io_error| RobotError::io(arg, io_error))?
+                .filter_map(|dir_entry| dir_entry.ok().map(|dir_entry| dir_entry.path()))
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "robot")
+                })
+                .collect();
+            found.sort();
+            paths.extend(found);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "robot")
+        {
+            paths.push(path.to_path_buf());
+        }
+    }
+
+    let mut failed = 0;
+
+    for path in &paths {
+        let result = fs::read_to_string(path)
+            .map_err(|io_error| io_error.to_string())
+            .and_then(|text| {
+                run_scenario(&board, &text).map_err(|scenario_error| scenario_error.to_string())
+            });
+
+        match result {
+            Ok(failures) if failures.is_empty() => println!("PASS {}", path.display()),
+            Ok(failures) => {
+                failed += 1;
+                println!("FAIL {}", path.display());
+                for failure in failures {
+                    println!("  {}", failure.to_string().replace('\n', "\n  "));
+                }
+            }
+            Err(error) => {
+                failed += 1;
+                println!("FAIL {}: {}", path.display(), error);
+            }
+        }
+    }
+
+    println!("{} passed, {} failed", paths.len() - failed, failed);
+
+    if failed > 0 {
+        return Err(RobotError::ScenariosFailed(failed));
+    }
+
+    Ok(())
+}
+
+/// The value after `flag` in the args, if the flag is there at all.
+fn flag_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Result<Option<T>, ConfigError> {
+    let index = match args.iter().position(|arg| arg == flag) {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+
+    let value = args
+        .get(index + 1)
+        .ok_or_else(|| ConfigError::MissingFlagValue(flag.to_string()))?;
+
+    value
+        .parse::<T>()
+        .map(Some)
+        .map_err(|_| ConfigError::BadFlagValue {
+            flag: flag.to_string(),
+            value: value.to_string(),
+        })
+}
 
-                if is_board_valid(&new_board) {
-                    board = new_board
-                };
-            })
-            .unwrap_or_else(|parsing_error| eprintln!("{}", parsing_error))
+fn without_flag(args: &[String], flag: &str) -> Vec<String> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) => [&args[..index], args.get(index + 2..).unwrap_or(&[])].concat(),
+        None => args.to_vec(),
     }
 }
@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::{Error, Formatter};
+
+use crate::game_model::{Board, Item, Robot, Terrain};
+use crate::geo::grid::{GridShape, SquareGrid};
+use crate::geo::{Compass, Direction, Vector};
+use crate::map_format::MapFormatError::{
+    EmptyMap, MultipleRobots, RaggedRow, TooLarge, UnpairedTeleporter, UnrecognisedGlyph,
+};
+
+/// Problems reading a board from the same text that MAP prints.
+#[derive(Debug, Eq, PartialEq)]
+pub enum MapFormatError {
+    EmptyMap,
+    RaggedRow(usize),
+    UnrecognisedGlyph(char, Vector),
+    MultipleRobots(Vector),
+    UnpairedTeleporter(char),
+    /// More columns or rows than the coordinates can count.
+    TooLarge {
+        width: usize,
+        height: usize,
+    },
+}
+
+impl std::fmt::Display for MapFormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            EmptyMap => write!(f, "Map has no rows"),
+            RaggedRow(row) => write!(f, "Map row {} has a different width to the first", row),
+            UnrecognisedGlyph(glyph, location) => {
+                write!(f, "Unrecognised map glyph '{}' at: {}", glyph, location)
+            }
+            MultipleRobots(location) => write!(f, "Second robot in map at: {}", location),
+            UnpairedTeleporter(glyph) => {
+                write!(f, "Teleporter '{}' does not appear exactly twice", glyph)
+            }
+            TooLarge { width, height } => write!(f, "Map is too large: {}x{}", width, height),
+        }
+    }
+}
+
+impl std::error::Error for MapFormatError {}
+
+/// Parses a single-level square board drawn as MAP draws it, top row first.
+///
+/// `0` or `.` is an empty cell, `X` an obstacle, `C` a charger, `G` a goal, a lowercase letter
+/// other than `v` an item, `#` carpet, `~` ice, `@` a pit, and `^`, `>`, `v` or `<` the robot. A
+/// digit from `1` to `9` is one end of a teleporter, linked to the only other cell with the same
+/// digit, and `⇑`, `⇒`, `⇓` or `⇐` a conveyor. The diagonal robot glyphs `↗`, `↖`, `↘` and `↙`
+/// and conveyors `⇗`, `⇖`, `⇘` and `⇙` make the board eight-way.
+pub fn parse_map(text: &str) -> Result<Board, MapFormatError> {
+    let rows: Vec<&str> = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|row| !row.is_empty())
+        .collect();
+
+    let width = rows.first().ok_or(EmptyMap)?.chars().count();
+
+    if let Some(ragged_row) = rows.iter().position(|row| row.chars().count() != width) {
+        return Err(RaggedRow(ragged_row + 1));
+    }
+
+    let (right, top) = match (i16::try_from(width - 1), i16::try_from(rows.len() - 1)) {
+        (Ok(right), Ok(top)) => (right, top),
+        _ => {
+            return Err(TooLarge {
+                width,
+                height: rows.len(),
+            })
+        }
+    };
+    let mut board = Board::empty_with_corner(&Vector::new(right, top));
+    let mut teleporters: BTreeMap<char, Vec<Vector>> = BTreeMap::new();
+
+    for (row_index, row) in rows.iter().enumerate() {
+        for (column_index, glyph) in row.chars().enumerate() {
+            let location = Vector::new(column_index as i16, top - row_index as i16);
+
+            board = match glyph {
+                '0' | '.' => board,
+                'X' => board.with_obstacle_at(location),
+                'C' => board.with_charger_at(location),
+                'G' => board.with_goal_at(location),
+                '#' => board.with_terrain_at(location, Terrain::Carpet),
+                '~' => board.with_terrain_at(location, Terrain::Ice),
+                '@' => board.with_terrain_at(location, Terrain::Pit),
+                '1'..='9' => {
+                    teleporters.entry(glyph).or_default().push(location);
+                    board
+                }
+                '⇑' => board.with_conveyor_at(location, Direction::North),
+                '⇒' => board.with_conveyor_at(location, Direction::East),
+                '⇓' => board.with_conveyor_at(location, Direction::South),
+                '⇐' => board.with_conveyor_at(location, Direction::West),
+                '⇗' => board.with_conveyor_at(location, Direction::NorthEast),
+                '⇖' => board.with_conveyor_at(location, Direction::NorthWest),
+                '⇘' => board.with_conveyor_at(location, Direction::SouthEast),
+                '⇙' => board.with_conveyor_at(location, Direction::SouthWest),
+                '^' | '>' | 'v' | '<' | '↗' | '↖' | '↘' | '↙' if board.robot.is_some() => {
+                    return Err(MultipleRobots(location))
+                }
+                '^' => board.with_robot(Robot::new(location, Direction::North)),
+                '>' => board.with_robot(Robot::new(location, Direction::East)),
+                'v' => board.with_robot(Robot::new(location, Direction::South)),
+                '<' => board.with_robot(Robot::new(location, Direction::West)),
+                '↗' => board.with_robot(Robot::new(location, Direction::NorthEast)),
+                '↖' => board.with_robot(Robot::new(location, Direction::NorthWest)),
+                '↘' => board.with_robot(Robot::new(location, Direction::SouthEast)),
+                '↙' => board.with_robot(Robot::new(location, Direction::SouthWest)),
+                name if Item::is_name(name) => board.with_item_at(location, Item(name)),
+                _ => return Err(UnrecognisedGlyph(glyph, location)),
+            };
+        }
+    }
+
+    for (glyph, ends) in teleporters {
+        board = match *ends.as_slice() {
+            [first, second] => board.with_teleporters_at(first, second),
+            _ => return Err(UnpairedTeleporter(glyph)),
+        };
+    }
+
+    if rows.iter().any(|row| row.chars().any(is_diagonal)) {
+        board = board.with_grid(GridShape::Square(SquareGrid {
+            compass: Compass::EightWay,
+        }));
+    }
+
+    Ok(board)
+}
+
+fn is_diagonal(glyph: char) -> bool {
+    matches!(glyph, '↗' | '↖' | '↘' | '↙' | '⇗' | '⇖' | '⇘' | '⇙')
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::Command;
+    use crate::game_execution::output_from_command;
+    use crate::game_model::{Board, Item, Robot, Terrain};
+    use crate::geo::grid::{GridShape, SquareGrid};
+    use crate::geo::Compass::EightWay;
+    use crate::geo::Direction::*;
+    use crate::geo::Vector;
+    use crate::map_format::parse_map;
+    use crate::map_format::MapFormatError::*;
+
+    #[test]
+    fn parse_empty_map() {
+        assert_eq!(parse_map("\n\n"), Err(EmptyMap))
+    }
+
+    #[test]
+    fn parse_map_too_wide_for_the_coordinates() {
+        let row = "0".repeat(i16::MAX as usize + 2);
+
+        assert_eq!(
+            parse_map(&row),
+            Err(TooLarge {
+                width: i16::MAX as usize + 2,
+                height: 1
+            })
+        )
+    }
+
+    #[test]
+    fn parse_map_as_wide_as_the_coordinates_allow() {
+        let row = "0".repeat(i16::MAX as usize + 1);
+
+        assert_eq!(
+            parse_map(&row).map(|board| board.bounds.top_right),
+            Ok(Vector::new(i16::MAX, 0))
+        )
+    }
+
+    #[test]
+    fn parse_map_with_contents() {
+        let expected_board = Board::empty_with_corner(&Vector::new(2, 1))
+            .with_obstacle_at(Vector::new(0, 1))
+            .with_goal_at(Vector::new(2, 1))
+            .with_charger_at(Vector::new(1, 0))
+            .with_item_at(Vector::new(2, 0), Item('a'))
+            .with_robot(Robot::new(Vector::new(0, 0), East));
+
+        assert_eq!(parse_map("X.G\n>Ca\n"), Ok(expected_board))
+    }
+
+    #[test]
+    fn parse_map_terrain() {
+        let expected_board = Board::empty_with_corner(&Vector::new(2, 0))
+            .with_terrain_at(Vector::new(0, 0), Terrain::Carpet)
+            .with_terrain_at(Vector::new(1, 0), Terrain::Ice)
+            .with_terrain_at(Vector::new(2, 0), Terrain::Pit);
+
+        assert_eq!(parse_map("#~@\n"), Ok(expected_board))
+    }
+
+    #[test]
+    fn parse_map_teleporters_and_conveyors() {
+        let expected_board = Board::empty_with_corner(&Vector::new(2, 1))
+            .with_teleporters_at(Vector::new(0, 1), Vector::new(2, 0))
+            .with_conveyor_at(Vector::new(1, 1), East)
+            .with_conveyor_at(Vector::new(0, 0), North);
+
+        assert_eq!(parse_map("1⇒0\n⇑01\n"), Ok(expected_board))
+    }
+
+    #[test]
+    fn parse_map_diagonals() {
+        let expected_board = Board::empty_with_corner(&Vector::new(1, 0))
+            .with_grid(GridShape::Square(SquareGrid { compass: EightWay }))
+            .with_conveyor_at(Vector::new(0, 0), SouthWest)
+            .with_robot(Robot::new(Vector::new(1, 0), NorthEast));
+
+        assert_eq!(parse_map("⇙↗\n"), Ok(expected_board))
+    }
+
+    #[test]
+    fn parse_map_reads_back_every_glyph_map_draws() {
+        let teleporters = (0..9).fold(Board::empty_with_corner(&Vector::new(8, 4)), |board, x| {
+            board.with_teleporters_at(Vector::new(x, 4), Vector::new(8 - x, 3))
+        });
+        let board = [
+            North, South, East, West, NorthEast, NorthWest, SouthEast, SouthWest,
+        ]
+        .iter()
+        .enumerate()
+        .fold(teleporters, |board, (x, direction)| {
+            board.with_conveyor_at(Vector::new(x as i16, 2), *direction)
+        })
+        .with_grid(GridShape::Square(SquareGrid { compass: EightWay }))
+        .with_obstacle_at(Vector::new(0, 1))
+        .with_charger_at(Vector::new(1, 1))
+        .with_goal_at(Vector::new(2, 1))
+        .with_item_at(Vector::new(3, 1), Item('a'))
+        .with_terrain_at(Vector::new(4, 1), Terrain::Carpet)
+        .with_terrain_at(Vector::new(5, 1), Terrain::Ice)
+        .with_terrain_at(Vector::new(6, 1), Terrain::Pit)
+        .with_robot(Robot::new(Vector::new(0, 0), SouthWest));
+
+        let map = output_from_command(&board, &Command::Map).unwrap();
+
+        assert_eq!(parse_map(&map), Ok(board))
+    }
+
+    #[test]
+    fn parse_map_unpaired_teleporter() {
+        assert_eq!(parse_map("1⇒0\n⇑02\n"), Err(UnpairedTeleporter('1')))
+    }
+
+    #[test]
+    fn parse_map_ragged_row() {
+        assert_eq!(parse_map("000\n00\n"), Err(RaggedRow(2)))
+    }
+
+    #[test]
+    fn parse_map_unrecognised_glyph() {
+        assert_eq!(
+            parse_map("00\n0?\n"),
+            Err(UnrecognisedGlyph('?', Vector::new(1, 0)))
+        )
+    }
+
+    #[test]
+    fn parse_map_two_robots() {
+        assert_eq!(
+            parse_map("^0\n0v\n"),
+            Err(MultipleRobots(Vector::new(1, 0)))
+        )
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::analysis::{free_cells, reachable_locations};
+use crate::commands::Command;
+use crate::game_execution::{
+    successors, update_board_from_command, validate_board, ExecutionError,
+};
+use crate::game_model::{Board, Robot};
+use crate::geo::RelativeDirection::{Left, Right};
+use crate::geo::{Direction, Vector};
+use crate::planning::Step;
+
+/// A route that takes the robot over every free cell it can reach.
+#[derive(Debug, Eq, PartialEq)]
+pub struct CoverageRoute {
+    pub commands: Vec<Command>,
+    /// How many cells the route stops on, including the one the robot starts on.
+    pub covered: usize,
+    /// Free cells the robot cannot get to, ordered by level, then column, then row.
+    pub unreachable: Vec<Vector>,
+}
+
+/// Plans a route from where the robot stands that visits every free cell it can reach, or
+/// `None` if there is no robot.
+///
+/// The route is built by repeatedly heading for the nearest cell not yet visited, counting each
+/// MOVE, LEFT and RIGHT as one command, which keeps moves plus turns low without promising the
+/// shortest possible route. Energy is ignored.
+pub fn plan_coverage(board: &Board) -> Option<CoverageRoute> {
+    let robot = board.robot.as_ref()?.with_energy(None);
+    let board = board.with_robot(robot.clone()).without_history();
+
+    let reachable = reachable_locations(&board);
+    let free_cells = free_cells(&board);
+
+    let mut unreachable: Vec<Vector> = free_cells
+        .iter()
+        .filter(|location| !reachable.contains(location))
+        .copied()
+        .collect();
+    unreachable.sort_by_key(|location| (location.z, location.x, location.y));
+
+    let mut unvisited: HashSet<Vector> = free_cells
+        .into_iter()
+        .filter(|location| reachable.contains(location) && *location != robot.location)
+        .collect();
+
+    let mut commands = Vec::new();
+    let mut covered = 1;
+    let mut robot = robot;
+
+    while !unvisited.is_empty() {
+        let path = match path_to_nearest(&board, &robot, &unvisited) {
+            Some(path) => path,
+            None => break,
+        };
+
+        for (command, next_robot) in path {
+            if unvisited.remove(&next_robot.location) {
+                covered += 1;
+            }
+            commands.push(command);
+            robot = next_robot;
+        }
+    }
+
+    Some(CoverageRoute {
+        commands,
+        covered,
+        unreachable,
+    })
+}
+
+/// Runs the coverage route for the robot on `board`, adding its report to `output` once the whole
+/// route has run.
+pub fn cover(board: &Board, output: &mut Vec<String>) -> Result<Board, ExecutionError> {
+    let route = match plan_coverage(board) {
+        Some(route) => route,
+        None => return Ok(board.clone()),
+    };
+
+    let covered_board = route
+        .commands
+        .iter()
+        .try_fold(board.clone(), |board, command| {
+            let next_board = update_board_from_command(&board, command)?;
+
+            validate_board(&next_board).map(|_| next_board)
+        })?;
+
+    output.push(coverage_report(&route));
+
+    Ok(covered_board)
+}
+
+/// The output of COVER: the route, how many cells it covers, and any cells it cannot reach.
+pub fn coverage_report(route: &CoverageRoute) -> String {
+    let mut lines = Vec::new();
+
+    if !route.commands.is_empty() {
+        let commands: Vec<String> = route
+            .commands
+            .iter()
+            .map(|command| Step::Do(command.clone()).to_string())
+            .collect();
+
+        lines.push(commands.join(","));
+    }
+
+    lines.push(format!(
+        "COVERED {} CELLS IN {} COMMANDS",
+        route.covered,
+        route.commands.len()
+    ));
+
+    if !route.unreachable.is_empty() {
+        let unreachable: Vec<String> = route.unreachable.iter().map(Vector::to_string).collect();
+
+        lines.push(format!("UNREACHABLE {}", unreachable.join(" ")));
+    }
+
+    lines.join("\n")
+}
+
+/// The fewest commands, and where each leaves the robot, to reach any of `targets`.
+fn path_to_nearest(
+    board: &Board,
+    start: &Robot,
+    targets: &HashSet<Vector>,
+) -> Option<Vec<(Command, Robot)>> {
+    let mut visited: HashSet<(Vector, Direction)> = HashSet::new();
+    let mut parents: HashMap<(Vector, Direction), (Command, Robot)> = HashMap::new();
+    let mut queue: VecDeque<Robot> = VecDeque::new();
+
+    visited.insert((start.location, start.facing));
+    queue.push_back(start.clone());
+
+    while let Some(robot) = queue.pop_front() {
+        if targets.contains(&robot.location) {
+            let mut path = Vec::new();
+            let mut current = robot;
+
+            while let Some((command, parent)) = parents.get(&(current.location, current.facing)) {
+                path.push((command.clone(), current));
+                current = parent.clone();
+            }
+
+            path.reverse();
+            return Some(path);
+        }
+
+        for (command, next_board) in successors(
+            &board.with_robot(robot.clone()),
+            &[Command::Move, Command::Rotate(Left), Command::Rotate(Right)],
+        ) {
+            if let Some(next_robot) = next_board.robot {
+                if visited.insert((next_robot.location, next_robot.facing)) {
+                    parents.insert(
+                        (next_robot.location, next_robot.facing),
+                        (command, robot.clone()),
+                    );
+                    queue.push_back(next_robot);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::commands::Command;
+    use crate::game_execution::update_board_from_command;
+    use crate::game_execution::ExecutionError::OutOfEnergy;
+    use crate::game_model::{Board, EnergyModel, Ramp, Robot};
+    use crate::geo::Direction::*;
+    use crate::geo::RelativeDirection::Right;
+    use crate::geo::Vector;
+    use crate::planning::coverage::{cover, coverage_report, plan_coverage, CoverageRoute};
+
+    /// Every cell the robot stands on while following the route.
+    fn visited(board: &Board, commands: &[Command]) -> HashSet<Vector> {
+        let mut board = board.clone();
+        let mut visited: HashSet<Vector> = board.robot.iter().map(|robot| robot.location).collect();
+
+        for command in commands {
+            board = update_board_from_command(&board, command).unwrap();
+            visited.extend(board.robot.iter().map(|robot| robot.location));
+        }
+
+        visited
+    }
+
+    #[test]
+    fn coverage_without_robot() {
+        assert_eq!(
+            None,
+            plan_coverage(&Board::empty_with_corner(&Vector::new(2, 2)))
+        )
+    }
+
+    #[test]
+    fn coverage_of_corridor() {
+        let board = Board::empty_with_corner(&Vector::new(0, 2))
+            .with_robot(Robot::new(Vector::new(0, 0), North));
+
+        assert_eq!(
+            Some(CoverageRoute {
+                commands: vec![Command::Move, Command::Move],
+                covered: 3,
+                unreachable: vec![],
+            }),
+            plan_coverage(&board)
+        )
+    }
+
+    #[test]
+    fn coverage_turns_at_the_end_of_rows() {
+        let board = Board::empty_with_corner(&Vector::new(1, 1))
+            .with_robot(Robot::new(Vector::new(0, 0), North));
+
+        assert_eq!(
+            vec![
+                Command::Move,
+                Command::Rotate(Right),
+                Command::Move,
+                Command::Rotate(Right),
+                Command::Move,
+            ],
+            plan_coverage(&board).unwrap().commands
+        )
+    }
+
+    #[test]
+    fn coverage_visits_every_reachable_cell_around_obstacles() {
+        let board = Board::empty_with_corner(&Vector::new(4, 4))
+            .with_obstacle_at(Vector::new(1, 1))
+            .with_obstacle_at(Vector::new(2, 3))
+            .with_obstacle_at(Vector::new(3, 1))
+            .with_robot(Robot::new(Vector::new(0, 0), East));
+
+        let route = plan_coverage(&board).unwrap();
+
+        assert_eq!(22, route.covered);
+        assert_eq!(22, visited(&board, &route.commands).len());
+        assert!(route.unreachable.is_empty())
+    }
+
+    #[test]
+    fn coverage_reports_walled_off_cells() {
+        let board = Board::empty_with_corner(&Vector::new(2, 2))
+            .with_obstacle_at(Vector::new(1, 2))
+            .with_obstacle_at(Vector::new(2, 1))
+            .with_robot(Robot::new(Vector::new(0, 0), North));
+
+        let route = plan_coverage(&board).unwrap();
+
+        assert_eq!(vec![Vector::new(2, 2)], route.unreachable);
+        assert_eq!(6, visited(&board, &route.commands).len())
+    }
+
+    #[test]
+    fn cover_leaves_robot_at_end_of_route() {
+        let board = Board::empty_with_corner(&Vector::new(0, 2))
+            .with_robot(Robot::new(Vector::new(0, 0), North));
+
+        assert_eq!(
+            Ok(Some(Robot::new(Vector::new(0, 2), North))),
+            cover(&board, &mut Vec::new()).map(|board| board.robot)
+        )
+    }
+
+    #[test]
+    fn cover_out_of_energy_reports_nothing() {
+        let board = Board::empty_with_corner(&Vector::new(0, 2))
+            .with_energy_model(EnergyModel {
+                capacity: 1,
+                move_cost: 1,
+                rotate_cost: 1,
+                carpet_cost: 1,
+            })
+            .with_robot(Robot::new(Vector::new(0, 0), North).with_energy(Some(1)));
+        let mut output = Vec::new();
+
+        assert_eq!(
+            Err(OutOfEnergy {
+                required: 1,
+                remaining: 0
+            }),
+            cover(&board, &mut output)
+        );
+        assert!(output.is_empty())
+    }
+
+    #[test]
+    fn coverage_counts_only_cells_it_gets_to() {
+        // Taking the one-way ramp up first leaves the cell behind the robot out of reach.
+        let board = Board::empty_with_corner(&Vector::at_level(3, 0, 1))
+            .with_ramp_at(Vector::new(2, 0), Ramp::Up)
+            .with_robot(Robot::new(Vector::new(1, 0), East));
+
+        let route = plan_coverage(&board).unwrap();
+
+        assert_eq!(5, route.covered);
+        assert_eq!(5, visited(&board, &route.commands).len())
+    }
+
+    #[test]
+    fn coverage_report_lists_route_and_unreachable_cells() {
+        let board = Board::empty_with_corner(&Vector::new(1, 1))
+            .with_obstacle_at(Vector::new(0, 1))
+            .with_obstacle_at(Vector::new(1, 0))
+            .with_robot(Robot::new(Vector::new(0, 0), North));
+
+        assert_eq!(
+            "COVERED 1 CELLS IN 0 COMMANDS\nUNREACHABLE 1,1",
+            coverage_report(&plan_coverage(&board).unwrap())
+        )
+    }
+}
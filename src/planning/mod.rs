@@ -0,0 +1,571 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Error, Formatter};
+use std::iter;
+
+use crate::commands::Command;
+use crate::game_execution::{cells_entered, successors, validate_board, ExecutionError};
+use crate::game_model::{Board, Robot};
+use crate::geo::RelativeDirection::{Left, Right};
+use crate::geo::{Direction, Vector};
+use crate::planning::PlanError::{BadGoal, BadStart, NoPlan, SameStart};
+
+pub mod coverage;
+
+/// A robot to plan for and the cell it has to get to.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Agent {
+    pub robot: Robot,
+    pub goal: Vector,
+}
+
+impl Agent {
+    pub fn new(location: Vector, facing: Direction, goal: Vector) -> Agent {
+        Agent {
+            robot: Robot::new(location, facing),
+            goal,
+        }
+    }
+}
+
+/// What a robot does on one tick of a plan.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Step {
+    Do(Command),
+    Wait,
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            Step::Do(Command::Move) => write!(f, "MOVE"),
+            Step::Do(Command::Rotate(Left)) => write!(f, "LEFT"),
+            Step::Do(Command::Rotate(Right)) => write!(f, "RIGHT"),
+            Step::Do(command) => write!(f, "{:?}", command),
+            Step::Wait => write!(f, "WAIT"),
+        }
+    }
+}
+
+/// Steps for each agent, in the same order as the agents, that get every one of them to its
+/// goal without two robots ever sharing a cell or swapping places.
+#[derive(Debug, Eq, PartialEq)]
+pub struct FleetPlan {
+    pub paths: Vec<Vec<Step>>,
+}
+
+impl FleetPlan {
+    /// Ticks until the last robot reaches its goal.
+    pub fn makespan(&self) -> usize {
+        self.paths.iter().map(Vec::len).max().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PlanError {
+    BadStart { agent: usize, error: ExecutionError },
+    BadGoal { agent: usize, error: ExecutionError },
+    SameStart { first: usize, second: usize },
+    NoPlan { agent: usize },
+}
+
+impl std::fmt::Display for PlanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            BadStart { agent, error } => write!(f, "Robot {} cannot start there: {}", agent, error),
+            BadGoal { agent, error } => write!(f, "Robot {} cannot end there: {}", agent, error),
+            SameStart { first, second } => {
+                write!(f, "Robots {} and {} start in the same cell", first, second)
+            }
+            NoPlan { agent } => write!(f, "No collision-free plan for robot {}", agent),
+        }
+    }
+}
+
+impl std::error::Error for PlanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BadStart { error, .. } | BadGoal { error, .. } => Some(error),
+            SameStart { .. } | NoPlan { .. } => None,
+        }
+    }
+}
+
+/// Where a robot is, which way it faces, and on which tick.
+type State = (Vector, Direction, usize);
+
+/// Cells and moves already claimed by robots planned earlier, by tick.
+#[derive(Default)]
+struct Reservations {
+    cells: HashSet<(Vector, usize)>,
+    moves: HashSet<(Vector, Vector, usize)>,
+    /// Goals of robots that have finished, and the tick from which they stay there.
+    parked: HashMap<Vector, usize>,
+}
+
+impl Reservations {
+    fn is_cell_free(&self, location: &Vector, tick: usize) -> bool {
+        !self.cells.contains(&(*location, tick))
+            && self
+                .parked
+                .get(location)
+                .is_none_or(|parked_from| tick < *parked_from)
+    }
+
+    fn is_free_from(&self, location: &Vector, tick: usize) -> bool {
+        !self.parked.contains_key(location)
+            && !self
+                .cells
+                .iter()
+                .any(|(reserved, reserved_tick)| reserved == location && *reserved_tick >= tick)
+    }
+
+    /// Claims the cells a robot is in on each tick, the last of which is where it ends the tick.
+    fn reserve(&mut self, occupied: &[Vec<Vector>]) {
+        for (tick, cells) in occupied.iter().enumerate() {
+            for cell in cells {
+                self.cells.insert((*cell, tick));
+            }
+
+            if let (Some(location), Some(next_location)) = (
+                cells.last(),
+                occupied.get(tick + 1).and_then(|cells| cells.last()),
+            ) {
+                self.moves.insert((*location, *next_location, tick));
+            }
+        }
+
+        if let Some(goal) = occupied.last().and_then(|cells| cells.last()) {
+            self.parked.insert(*goal, occupied.len() - 1);
+        }
+    }
+}
+
+/// Plans every agent in turn, each one avoiding the cells and moves of the agents planned before
+/// it. If an agent cannot be planned, it is moved to the front of the order and planning starts
+/// again, giving up when the agent that fails is already first or every agent has had a turn at
+/// the front.
+///
+/// Robots move, turn and wait as MOVE, LEFT and RIGHT allow on `board`, ignoring energy and any
+/// robot already on the board. A robot sliding on ice or going through a teleporter claims every
+/// cell it passes through on that tick. Planning in turn is quick but can fail where a joint
+/// search would succeed.
+pub fn plan_all(board: &Board, agents: &[Agent]) -> Result<FleetPlan, PlanError> {
+    let board = Board {
+        energy_model: None,
+        ..board.without_robot().without_history()
+    };
+
+    for (agent, Agent { robot, goal }) in agents.iter().enumerate() {
+        validate_board(&board.with_robot(robot.clone()))
+            .map_err(|error| BadStart { agent, error })?;
+        validate_board(&board.with_robot(robot.with_position(*goal)))
+            .map_err(|error| BadGoal { agent, error })?;
+
+        if let Some(first) = agents[..agent]
+            .iter()
+            .position(|other| other.robot.location == robot.location)
+        {
+            return Err(SameStart {
+                first,
+                second: agent,
+            });
+        }
+    }
+
+    let horizon = board_size(&board) * 4 * agents.len().max(1);
+    let mut order: Vec<usize> = (0..agents.len()).collect();
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+
+        match plan_in_order(&board, agents, &order, horizon) {
+            Ok(paths) => return Ok(FleetPlan { paths }),
+            Err(agent) if order[0] == agent || attempts >= agents.len() => {
+                return Err(NoPlan { agent })
+            }
+            Err(agent) => {
+                order.retain(|index| *index != agent);
+                order.insert(0, agent);
+            }
+        }
+    }
+}
+
+/// Plans the agents in `order`, returning their steps in their original order, or the first
+/// agent with no plan.
+fn plan_in_order(
+    board: &Board,
+    agents: &[Agent],
+    order: &[usize],
+    horizon: usize,
+) -> Result<Vec<Vec<Step>>, usize> {
+    let mut reservations = Reservations::default();
+    let mut paths: Vec<Vec<Step>> = vec![Vec::new(); agents.len()];
+
+    for index in order {
+        let (steps, occupied) =
+            plan_agent(board, &agents[*index], &reservations, horizon).ok_or(*index)?;
+
+        reservations.reserve(&occupied);
+        paths[*index] = steps;
+    }
+
+    Ok(paths)
+}
+
+fn board_size(board: &Board) -> usize {
+    let size = |low: i16, high: i16| (i32::from(high) - i32::from(low) + 1) as usize;
+
+    size(board.bounds.bottom_left.x, board.bounds.top_right.x)
+        * size(board.bounds.bottom_left.y, board.bounds.top_right.y)
+        * size(board.bounds.bottom_left.z, board.bounds.top_right.z)
+}
+
+/// A breadth-first search through time for the fewest steps to the agent's goal, returning the
+/// steps and the cells the robot is in on each tick, starting with where it is now.
+fn plan_agent(
+    board: &Board,
+    agent: &Agent,
+    reservations: &Reservations,
+    horizon: usize,
+) -> Option<(Vec<Step>, Vec<Vec<Vector>>)> {
+    let start = agent.robot.with_energy(None);
+    let mut parents: HashMap<State, (State, Step, Vec<Vector>)> = HashMap::new();
+    let mut queue: VecDeque<(Robot, usize)> = VecDeque::new();
+
+    parents.insert(
+        (start.location, start.facing, 0),
+        (
+            (start.location, start.facing, 0),
+            Step::Wait,
+            vec![start.location],
+        ),
+    );
+    queue.push_back((start, 0));
+
+    while let Some((robot, tick)) = queue.pop_front() {
+        if robot.location == agent.goal && reservations.is_free_from(&agent.goal, tick) {
+            return Some(unwind(&parents, (robot.location, robot.facing, tick)));
+        }
+
+        if tick >= horizon {
+            continue;
+        }
+
+        let current = board.with_robot(robot.clone());
+        let steps = successors(
+            &current,
+            &[Command::Move, Command::Rotate(Left), Command::Rotate(Right)],
+        )
+        .into_iter()
+        .map(|(command, next_board)| (Step::Do(command), next_board))
+        .chain(iter::once((Step::Wait, current.clone())));
+
+        for (step, next_board) in steps {
+            let next_robot = next_board.robot;
+
+            let cells = match (&step, &next_robot) {
+                (Step::Do(Command::Move), Some(_)) => cells_entered(&current, &robot),
+                (_, Some(next_robot)) => vec![next_robot.location],
+                (_, None) => continue,
+            };
+
+            let next_robot = match next_robot {
+                Some(next_robot)
+                    if cells
+                        .iter()
+                        .all(|cell| reservations.is_cell_free(cell, tick + 1))
+                        && !reservations.moves.contains(&(
+                            next_robot.location,
+                            robot.location,
+                            tick,
+                        )) =>
+                {
+                    next_robot
+                }
+                _ => continue,
+            };
+
+            let state = (next_robot.location, next_robot.facing, tick + 1);
+
+            if let Entry::Vacant(entry) = parents.entry(state) {
+                entry.insert(((robot.location, robot.facing, tick), step, cells));
+                queue.push_back((next_robot, tick + 1));
+            }
+        }
+    }
+
+    None
+}
+
+fn unwind(
+    parents: &HashMap<State, (State, Step, Vec<Vector>)>,
+    end: State,
+) -> (Vec<Step>, Vec<Vec<Vector>>) {
+    let mut steps = Vec::new();
+    let mut occupied = Vec::new();
+    let mut state = end;
+
+    loop {
+        let (parent, step, cells) = &parents[&state];
+
+        occupied.push(cells.clone());
+
+        if state.2 == 0 {
+            break;
+        }
+
+        steps.push(step.clone());
+        state = *parent;
+    }
+
+    steps.reverse();
+    occupied.reverse();
+
+    (steps, occupied)
+}
+
+/// The output of PLAN_ALL: each robot's steps on its own line, then the makespan.
+pub fn plan_report(board: &Board, agents: &[Agent]) -> String {
+    match plan_all(board, agents) {
+        Ok(plan) => {
+            let mut lines: Vec<String> = plan
+                .paths
+                .iter()
+                .enumerate()
+                .map(|(index, steps)| {
+                    let steps: Vec<String> = steps.iter().map(Step::to_string).collect();
+
+                    format!("{}: {}", index, steps.join(","))
+                        .trim_end()
+                        .to_string()
+                })
+                .collect();
+
+            lines.push(format!("MAKESPAN {}", plan.makespan()));
+            lines.join("\n")
+        }
+        Err(error) => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::Command;
+    use crate::game_execution::ExecutionError::{Occupied, OutOfBounds};
+    use crate::game_execution::{update_board_from_command, validate_board};
+    use crate::game_model::{Board, Robot, Terrain};
+    use crate::geo::Direction;
+    use crate::geo::Direction::*;
+    use crate::geo::Vector;
+    use crate::planning::PlanError::*;
+    use crate::planning::{plan_all, plan_report, Agent, FleetPlan, Step};
+
+    fn agent(x: i16, y: i16, facing: Direction, goal: Vector) -> Agent {
+        Agent {
+            robot: Robot::new(Vector::new(x, y), facing),
+            goal,
+        }
+    }
+
+    /// Plays every robot's steps together, checking no two robots ever share a cell, and
+    /// returns where each ends up.
+    fn play(board: &Board, agents: &[Agent], plan: &FleetPlan) -> Vec<Vector> {
+        let mut robots: Vec<Robot> = agents.iter().map(|agent| agent.robot.clone()).collect();
+
+        for tick in 0..plan.makespan() {
+            robots = robots
+                .iter()
+                .zip(&plan.paths)
+                .map(|(robot, steps)| match steps.get(tick) {
+                    Some(Step::Do(command)) => {
+                        update_board_from_command(&board.with_robot(robot.clone()), command)
+                            .unwrap()
+                            .robot
+                            .unwrap()
+                    }
+                    _ => robot.clone(),
+                })
+                .collect();
+
+            for (index, robot) in robots.iter().enumerate() {
+                assert!(validate_board(&board.with_robot(robot.clone())).is_ok());
+                assert!(robots[(index + 1)..]
+                    .iter()
+                    .all(|other| other.location != robot.location));
+            }
+        }
+
+        robots.iter().map(|robot| robot.location).collect()
+    }
+
+    #[test]
+    fn plan_single_robot() {
+        let board = Board::empty_with_corner(&Vector::new(2, 2));
+        let agents = vec![agent(0, 0, North, Vector::new(0, 2))];
+
+        assert_eq!(
+            Ok(FleetPlan {
+                paths: vec![vec![Step::Do(Command::Move), Step::Do(Command::Move)]]
+            }),
+            plan_all(&board, &agents)
+        )
+    }
+
+    #[test]
+    fn plan_on_a_board_as_wide_as_the_coordinates_allow() {
+        let board = Board::empty_with_corner(&Vector::new(i16::MAX, 0));
+        let agents = vec![agent(0, 0, East, Vector::new(1, 0))];
+
+        assert_eq!(
+            Ok(FleetPlan {
+                paths: vec![vec![Step::Do(Command::Move)]]
+            }),
+            plan_all(&board, &agents)
+        )
+    }
+
+    #[test]
+    fn plan_head_on() {
+        let board = Board::empty_with_corner(&Vector::new(4, 1));
+        let agents = vec![
+            agent(0, 0, East, Vector::new(4, 0)),
+            agent(4, 0, West, Vector::new(0, 0)),
+        ];
+
+        let plan = plan_all(&board, &agents).unwrap();
+
+        assert_eq!(
+            vec![Vector::new(4, 0), Vector::new(0, 0)],
+            play(&board, &agents, &plan)
+        )
+    }
+
+    #[test]
+    fn plan_crossing() {
+        let board = Board::empty_with_corner(&Vector::new(4, 4));
+        let agents = vec![
+            agent(0, 2, East, Vector::new(4, 2)),
+            agent(2, 0, North, Vector::new(2, 4)),
+        ];
+
+        let plan = plan_all(&board, &agents).unwrap();
+
+        assert_eq!(5, plan.makespan());
+        assert_eq!(
+            vec![Vector::new(4, 2), Vector::new(2, 4)],
+            play(&board, &agents, &plan)
+        )
+    }
+
+    #[test]
+    fn plan_one_lane_swap_is_impossible() {
+        let board = Board::empty_with_corner(&Vector::new(3, 0));
+        let agents = vec![
+            agent(0, 0, East, Vector::new(3, 0)),
+            agent(3, 0, West, Vector::new(0, 0)),
+        ];
+
+        assert!(matches!(plan_all(&board, &agents), Err(NoPlan { .. })))
+    }
+
+    #[test]
+    fn plan_start_on_obstacle() {
+        let board =
+            Board::empty_with_corner(&Vector::new(2, 2)).with_obstacle_at(Vector::new(1, 1));
+        let agents = vec![agent(1, 1, North, Vector::new(0, 0))];
+
+        assert_eq!(
+            Err(BadStart {
+                agent: 0,
+                error: Occupied(Vector::new(1, 1))
+            }),
+            plan_all(&board, &agents)
+        )
+    }
+
+    #[test]
+    fn plan_goal_on_obstacle() {
+        let board =
+            Board::empty_with_corner(&Vector::new(2, 2)).with_obstacle_at(Vector::new(1, 1));
+        let agents = vec![
+            agent(0, 0, North, Vector::new(0, 2)),
+            agent(2, 0, North, Vector::new(1, 1)),
+        ];
+
+        assert_eq!(
+            Err(BadGoal {
+                agent: 1,
+                error: Occupied(Vector::new(1, 1))
+            }),
+            plan_all(&board, &agents)
+        )
+    }
+
+    #[test]
+    fn plan_goal_off_the_board() {
+        let board = Board::empty_with_corner(&Vector::new(2, 2));
+        let agents = vec![agent(0, 0, North, Vector::new(0, 3))];
+
+        assert_eq!(
+            Err(BadGoal {
+                agent: 0,
+                error: OutOfBounds(Vector::new(0, 3))
+            }),
+            plan_all(&board, &agents)
+        )
+    }
+
+    #[test]
+    fn plan_two_robots_in_the_same_cell() {
+        let board = Board::empty_with_corner(&Vector::new(2, 2));
+        let agents = vec![
+            agent(0, 0, North, Vector::new(0, 2)),
+            agent(1, 1, North, Vector::new(1, 2)),
+            agent(0, 0, East, Vector::new(2, 0)),
+        ];
+
+        assert_eq!(
+            Err(SameStart {
+                first: 0,
+                second: 2
+            }),
+            plan_all(&board, &agents)
+        )
+    }
+
+    #[test]
+    fn plan_waits_for_a_robot_sliding_past() {
+        let board = Board::empty_with_corner(&Vector::new(4, 1))
+            .with_terrain_at(Vector::new(1, 0), Terrain::Ice)
+            .with_terrain_at(Vector::new(2, 0), Terrain::Ice)
+            .with_terrain_at(Vector::new(3, 0), Terrain::Ice);
+        let agents = vec![
+            agent(0, 0, East, Vector::new(4, 0)),
+            agent(2, 1, South, Vector::new(2, 0)),
+        ];
+
+        assert_eq!(
+            Ok(FleetPlan {
+                paths: vec![
+                    vec![Step::Do(Command::Move)],
+                    vec![Step::Wait, Step::Do(Command::Move)]
+                ]
+            }),
+            plan_all(&board, &agents)
+        )
+    }
+
+    #[test]
+    fn plan_report_lists_steps_and_makespan() {
+        let board = Board::empty_with_corner(&Vector::new(2, 2));
+        let agents = vec![
+            agent(0, 0, North, Vector::new(0, 1)),
+            agent(2, 2, South, Vector::new(2, 2)),
+        ];
+
+        assert_eq!("0: MOVE\n1:\nMAKESPAN 1", plan_report(&board, &agents))
+    }
+}
@@ -0,0 +1,706 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt::{Error, Formatter};
+
+use crate::analysis::reachable_locations;
+use crate::commands::parsing::{parse_direction, parse_location};
+use crate::commands::Command;
+use crate::game_execution::{annotated_map_for, successors};
+use crate::game_model::{Board, EnergyModel, Item, Robot};
+use crate::geo::grid::{GridShape, SquareGrid};
+use crate::geo::RelativeDirection::{Left, Right};
+use crate::geo::{Compass, Direction, Vector};
+use crate::map_format::{parse_map, MapFormatError};
+use crate::puzzle::LevelError::{
+    BadDirective, BadMap, MissingMap, NoGoal, NoRobot, OverBudget, Unreachable, Unsavable,
+    Unsolvable,
+};
+
+const BUILTIN_LEVELS: [&str; 4] = [
+    include_str!("../../levels/01-first-steps.level"),
+    include_str!("../../levels/02-around-the-wall.level"),
+    include_str!("../../levels/03-fetch.level"),
+    include_str!("../../levels/04-charge-up.level"),
+];
+
+/// A puzzle: get the robot from its start onto a goal cell, carrying the required items, within
+/// the command budget.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Level {
+    pub name: String,
+    pub board: Board,
+    pub required_items: Vec<Item>,
+    pub command_budget: Option<usize>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum LevelError {
+    BadDirective(String),
+    MissingMap,
+    BadMap(MapFormatError),
+    NoRobot,
+    NoGoal,
+    Unreachable(Vector),
+    Unsolvable,
+    OverBudget {
+        required: usize,
+        budget: usize,
+    },
+    /// The board is not one that a level can hold, so it cannot be saved as one.
+    Unsavable,
+}
+
+impl std::fmt::Display for LevelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            BadDirective(line) => write!(f, "Bad level directive: {}", line),
+            MissingMap => write!(f, "Level has no MAP section"),
+            BadMap(map_format_error) => write!(f, "Bad level map: {}", map_format_error),
+            NoRobot => write!(f, "Level has no robot start position"),
+            NoGoal => write!(f, "Level has no goal cells"),
+            Unreachable(location) => write!(f, "Unreachable from the start: {}", location),
+            Unsolvable => write!(f, "Level cannot be solved"),
+            OverBudget { required, budget } => write!(
+                f,
+                "Level needs {} commands but the budget is {}",
+                required, budget
+            ),
+            Unsavable => write!(f, "Only a single-level square board from 0,0 can be saved"),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BadMap(map_format_error) => Some(map_format_error),
+            _ => None,
+        }
+    }
+}
+
+impl Level {
+    pub fn is_complete(&self, board: &Board) -> bool {
+        board.robot.as_ref().is_some_and(|robot| {
+            board.goal_locations.contains(&robot.location)
+                && self.required_items.iter().all(|item| {
+                    let required = self.required_items.iter().filter(|i| *i == item).count();
+                    let carried = robot.inventory.iter().filter(|i| *i == item).count();
+
+                    carried >= required
+                })
+        })
+    }
+
+    pub fn is_out_of_commands(&self, commands_used: usize) -> bool {
+        self.command_budget
+            .is_some_and(|budget| commands_used >= budget)
+    }
+
+    /// 100 for completing the level, plus 10 for every command left in the budget.
+    pub fn score(&self, commands_used: usize) -> usize {
+        let unused_budget = self
+            .command_budget
+            .map_or(0, |budget| budget.saturating_sub(commands_used));
+
+        100 + 10 * unused_budget
+    }
+}
+
+/// Reads a level: `NAME`, `BUDGET`, `REQUIRE`, `BATTERY` (capacity, move, rotate and optional
+/// carpet costs), `CARRY` (items the robot starts with), `ROBOT` (where the robot starts, as PLACE
+/// takes it, for a map drawn without one) and `EIGHT_WAY` directives, then a `MAP` line followed
+/// by the board as MAP draws it.
+pub fn parse_level(text: &str) -> Result<Level, LevelError> {
+    let mut level = Level {
+        name: String::new(),
+        board: Board::empty_with_corner(&Vector::new(0, 0)),
+        required_items: Vec::new(),
+        command_budget: None,
+    };
+    let mut energy_model: Option<EnergyModel> = None;
+    let mut carried: Vec<Item> = Vec::new();
+    let mut start: Option<(Robot, String)> = None;
+    let mut eight_way = false;
+
+    let mut lines = text.lines();
+
+    loop {
+        let line = lines.next().ok_or(MissingMap)?.trim();
+        let (directive, value) = line.split_once(' ').unwrap_or((line, ""));
+        let bad_directive = || BadDirective(line.to_string());
+
+        match directive.to_lowercase().as_ref() {
+            "" => {}
+            "map" => break,
+            "name" => level.name = value.trim().to_string(),
+            "budget" => level.command_budget = Some(value.parse().map_err(|_| bad_directive())?),
+            "require" => level.required_items = parse_items(value).ok_or_else(bad_directive)?,
+            "carry" => carried = parse_items(value).ok_or_else(bad_directive)?,
+            "robot" => {
+                let robot = parse_robot(value).ok_or_else(bad_directive)?;
+                start = Some((robot, line.to_string()));
+            }
+            "eight_way" if value.is_empty() => eight_way = true,
+            "battery" => {
+                let costs = value
+                    .split(',')
+                    .map(|cost| cost.trim().parse::<u32>().map_err(|_| bad_directive()))
+                    .collect::<Result<Vec<u32>, LevelError>>()?;
+
+                energy_model = match costs[..] {
+                    [capacity, move_cost, rotate_cost] => Some(EnergyModel {
+                        capacity,
+                        move_cost,
+                        rotate_cost,
+                        carpet_cost: EnergyModel::DEFAULT_CARPET_COST,
+                    }),
+                    [capacity, move_cost, rotate_cost, carpet_cost] => Some(EnergyModel {
+                        capacity,
+                        move_cost,
+                        rotate_cost,
+                        carpet_cost,
+                    }),
+                    _ => return Err(bad_directive()),
+                }
+            }
+            _ => return Err(bad_directive()),
+        }
+    }
+
+    let map_text: Vec<&str> = lines.collect();
+    let mut board = parse_map(&map_text.join("\n")).map_err(BadMap)?;
+
+    if eight_way {
+        board = board.with_grid(GridShape::Square(SquareGrid {
+            compass: Compass::EightWay,
+        }));
+    }
+
+    if let Some((robot, line)) = start {
+        if board.robot.is_some() {
+            return Err(BadDirective(line));
+        }
+
+        board = board.with_robot(robot);
+    }
+
+    if let Some(energy_model) = energy_model {
+        board = board.with_energy_model(energy_model);
+
+        if let Some(robot) = &board.robot {
+            board = board.with_robot(robot.with_energy(Some(energy_model.capacity)));
+        }
+    }
+
+    if !carried.is_empty() {
+        let robot = board.robot.as_ref().ok_or(NoRobot)?;
+        board = board.with_robot(robot.with_inventory(carried));
+    }
+
+    level.board = board;
+
+    Ok(level)
+}
+
+/// A robot start as PLACE takes it, such as `0,0,NORTH`.
+fn parse_robot(text: &str) -> Option<Robot> {
+    match *text.split(',').map(str::trim).collect::<Vec<&str>>() {
+        [x, y, facing] => Some(Robot::new(
+            parse_location(&[x, y])?,
+            parse_direction(facing)?,
+        )),
+        _ => None,
+    }
+}
+
+/// Item names separated by commas, such as `a,b`.
+fn parse_items(text: &str) -> Option<Vec<Item>> {
+    text.split(',')
+        .map(
+            |name| match name.trim().chars().collect::<Vec<char>>()[..] {
+                [name] if Item::is_name(name) => Some(Item(name)),
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+fn item_names(items: &[Item]) -> String {
+    items
+        .iter()
+        .map(|Item(name)| name.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Writes a level as `parse_level` reads it, so that a board can be saved and loaded again with
+/// the items on it and carried by the robot. The robot is written as a `ROBOT` directive, so that
+/// whatever is in its cell is drawn on the map.
+///
+/// Only a single-level square board from 0,0 reads back, with the robot's battery full and
+/// nothing queued, and any other board is `Unsavable`.
+pub fn level_text(level: &Level) -> Result<String, LevelError> {
+    let board = &level.board;
+
+    if board.bounds.bottom_left != Vector::new(0, 0)
+        || board.bounds.top_right.z != 0
+        || matches!(board.grid, GridShape::Hex(_))
+    {
+        return Err(Unsavable);
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+
+    if !level.name.is_empty() {
+        lines.push(format!("NAME {}", level.name));
+    }
+
+    if let Some(budget) = level.command_budget {
+        lines.push(format!("BUDGET {}", budget));
+    }
+
+    if !level.required_items.is_empty() {
+        lines.push(format!("REQUIRE {}", item_names(&level.required_items)));
+    }
+
+    if let Some(energy_model) = board.energy_model {
+        lines.push(format!(
+            "BATTERY {},{},{},{}",
+            energy_model.capacity,
+            energy_model.move_cost,
+            energy_model.rotate_cost,
+            energy_model.carpet_cost
+        ));
+    }
+
+    if matches!(
+        board.grid,
+        GridShape::Square(SquareGrid {
+            compass: Compass::EightWay
+        })
+    ) {
+        lines.push("EIGHT_WAY".to_string());
+    }
+
+    if let Some(robot) = &board.robot {
+        lines.push(format!(
+            "ROBOT {},{},{}",
+            robot.location.x,
+            robot.location.y,
+            robot.facing.to_string().to_uppercase()
+        ));
+
+        if !robot.inventory.is_empty() {
+            lines.push(format!("CARRY {}", item_names(&robot.inventory)));
+        }
+    }
+
+    lines.push("MAP".to_string());
+    lines.push(annotated_map_for(&board.without_robot(), 0, |_| None));
+
+    Ok(lines.join("\n") + "\n")
+}
+
+pub fn builtin_levels() -> Vec<Level> {
+    BUILTIN_LEVELS
+        .iter()
+        .map(|text| parse_level(text).expect("built-in levels are well formed"))
+        .collect()
+}
+
+/// Where the robot is, which way it faces, what it carries and its energy, with the items still
+/// lying on the board by location.
+type SearchState = (
+    Vector,
+    Direction,
+    Vec<Item>,
+    Option<u32>,
+    Vec<(Vector, Item)>,
+);
+
+/// The state of the search a board is in, if it has a robot. Items on the board are part of it,
+/// because carrying one of two items with the same name leaves a different board depending on
+/// which was picked up.
+fn search_state(board: &Board) -> Option<SearchState> {
+    let robot = board.robot.as_ref()?;
+
+    let mut items: Vec<(Vector, Item)> = board
+        .item_locations
+        .iter()
+        .map(|(location, item)| (*location, *item))
+        .collect();
+    items.sort_by_key(|(location, _)| (location.x, location.y, location.z));
+
+    Some((
+        robot.location,
+        robot.facing,
+        robot.inventory.clone(),
+        robot.energy,
+        items,
+    ))
+}
+
+/// The fewest commands that complete the level, found by searching every MOVE, LEFT, RIGHT and
+/// PICK from the start, or `None` if it cannot be completed at all.
+pub fn shortest_solution(level: &Level) -> Option<usize> {
+    let mut visited: HashSet<SearchState> = HashSet::new();
+    let mut queue: VecDeque<(Board, usize)> = VecDeque::new();
+
+    visited.insert(search_state(&level.board)?);
+    queue.push_back((level.board.without_history(), 0));
+
+    while let Some((board, commands_used)) = queue.pop_front() {
+        if level.is_complete(&board) {
+            return Some(commands_used);
+        }
+
+        for (_, next_board) in successors(
+            &board,
+            &[
+                Command::Move,
+                Command::Rotate(Left),
+                Command::Rotate(Right),
+                Command::Pick,
+            ],
+        ) {
+            // A MOVE into a pit loses the robot, and with it any way on from that board.
+            let state = match search_state(&next_board) {
+                Some(state) => state,
+                None => continue,
+            };
+
+            if visited.insert(state) {
+                queue.push_back((next_board, commands_used + 1));
+            }
+        }
+    }
+
+    None
+}
+
+/// Checks a level can be completed within its budget, returning the fewest commands needed.
+pub fn verify_level(level: &Level) -> Result<usize, LevelError> {
+    if level.board.robot.is_none() {
+        return Err(NoRobot);
+    }
+
+    if level.board.goal_locations.is_empty() {
+        return Err(NoGoal);
+    }
+
+    let reachable = reachable_locations(&level.board);
+
+    if !level
+        .board
+        .goal_locations
+        .iter()
+        .any(|goal| reachable.contains(goal))
+    {
+        let mut goals: Vec<&Vector> = level.board.goal_locations.iter().collect();
+        goals.sort_by_key(|goal| (goal.x, goal.y));
+
+        return Err(Unreachable(*goals[0]));
+    }
+
+    for (location, item) in &level.board.item_locations {
+        if level.required_items.contains(item) && !reachable.contains(location) {
+            return Err(Unreachable(*location));
+        }
+    }
+
+    let required = shortest_solution(level).ok_or(Unsolvable)?;
+
+    match level.command_budget {
+        Some(budget) if required > budget => Err(OverBudget { required, budget }),
+        _ => Ok(required),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game_model::{Board, EnergyModel, Item, Ramp, Robot};
+    use crate::geo::grid::{GridShape, HexGrid, SquareGrid};
+    use crate::geo::Compass::EightWay;
+    use crate::geo::Direction::*;
+    use crate::geo::Vector;
+    use crate::puzzle::LevelError::*;
+    use crate::puzzle::{
+        builtin_levels, level_text, parse_level, shortest_solution, verify_level, Level,
+    };
+
+    fn corridor_level() -> Level {
+        parse_level(
+            "NAME Corridor\n\
+             BUDGET 4\n\
+             MAP\n\
+             >0G\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_level_directives() {
+        let level = parse_level(
+            "NAME Test level\n\
+             BUDGET 9\n\
+             REQUIRE a,b\n\
+             BATTERY 5,1,0\n\
+             MAP\n\
+             ^aG\n\
+             b00\n",
+        )
+        .unwrap();
+
+        assert_eq!("Test level", level.name);
+        assert_eq!(Some(9), level.command_budget);
+        assert_eq!(vec![Item('a'), Item('b')], level.required_items);
+        assert_eq!(Some(5), level.board.robot.and_then(|robot| robot.energy));
+        assert_eq!(
+            Some(EnergyModel::DEFAULT_CARPET_COST),
+            level.board.energy_model.map(|model| model.carpet_cost)
+        );
+    }
+
+    #[test]
+    fn parse_level_without_map() {
+        assert_eq!(Err(MissingMap), parse_level("NAME Nothing\n"))
+    }
+
+    #[test]
+    fn parse_level_bad_directive() {
+        assert_eq!(
+            Err(BadDirective("SPEED 3".to_string())),
+            parse_level("SPEED 3\nMAP\n^G\n")
+        )
+    }
+
+    #[test]
+    fn parse_level_carried_items() {
+        let level = parse_level(
+            "CARRY a,b\n\
+             MAP\n\
+             ^0G\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(vec![Item('a'), Item('b')]),
+            level.board.robot.map(|robot| robot.inventory)
+        );
+    }
+
+    #[test]
+    fn parse_level_carried_items_without_robot() {
+        assert_eq!(parse_level("CARRY a\nMAP\n00G\n"), Err(NoRobot))
+    }
+
+    #[test]
+    fn parse_level_item_named_like_a_robot() {
+        assert_eq!(
+            parse_level("REQUIRE v\nMAP\n^0G\n"),
+            Err(BadDirective("REQUIRE v".to_string()))
+        )
+    }
+
+    #[test]
+    fn level_text_reads_back() {
+        let level = parse_level(
+            "NAME Test level\n\
+             BUDGET 9\n\
+             REQUIRE a,b\n\
+             BATTERY 5,1,0,2\n\
+             CARRY c\n\
+             MAP\n\
+             ^aG\n\
+             b0X\n",
+        )
+        .unwrap();
+
+        assert_eq!(Ok(level.clone()), parse_level(&level_text(&level).unwrap()));
+    }
+
+    #[test]
+    fn level_text_of_a_board_carrying_items() {
+        let level = Level {
+            name: String::new(),
+            board: Board::empty_with_corner(&Vector::new(2, 0))
+                .with_item_at(Vector::new(2, 0), Item('k'))
+                .with_robot(Robot::new(Vector::new(0, 0), South).with_inventory(vec![Item('j')])),
+            required_items: Vec::new(),
+            command_budget: None,
+        };
+
+        assert_eq!(
+            Ok("ROBOT 0,0,SOUTH\nCARRY j\nMAP\n00k\n".to_string()),
+            level_text(&level)
+        );
+        assert_eq!(Ok(level.clone()), parse_level(&level_text(&level).unwrap()));
+    }
+
+    #[test]
+    fn level_text_keeps_what_is_under_the_robot() {
+        let level = Level {
+            name: String::new(),
+            board: Board::empty_with_corner(&Vector::new(2, 0))
+                .with_item_at(Vector::new(0, 0), Item('a'))
+                .with_goal_at(Vector::new(2, 0))
+                .with_robot(Robot::new(Vector::new(0, 0), East)),
+            required_items: Vec::new(),
+            command_budget: None,
+        };
+
+        let reloaded = parse_level(&level_text(&level).unwrap()).unwrap();
+
+        assert_eq!(level, reloaded);
+        assert_eq!(
+            Some(&Item('a')),
+            reloaded.board.item_locations.get(&Vector::new(0, 0))
+        );
+    }
+
+    #[test]
+    fn level_text_of_an_eight_way_board() {
+        let level = Level {
+            name: String::new(),
+            board: Board::empty_with_corner(&Vector::new(1, 1))
+                .with_grid(GridShape::Square(SquareGrid { compass: EightWay }))
+                .with_charger_at(Vector::new(1, 1))
+                .with_robot(Robot::new(Vector::new(1, 1), SouthWest)),
+            required_items: Vec::new(),
+            command_budget: None,
+        };
+
+        assert_eq!(Ok(level.clone()), parse_level(&level_text(&level).unwrap()));
+    }
+
+    #[test]
+    fn level_text_of_a_hex_or_stacked_board() {
+        let level = corridor_level();
+        let hex = Level {
+            board: level.board.with_grid(GridShape::Hex(HexGrid)),
+            ..level.clone()
+        };
+        let stacked = Level {
+            board: Board::empty_with_corner(&Vector::at_level(2, 0, 1)),
+            ..level
+        };
+
+        assert_eq!(Err(Unsavable), level_text(&hex));
+        assert_eq!(Err(Unsavable), level_text(&stacked));
+    }
+
+    #[test]
+    fn parse_level_robot_directive_and_robot_glyph() {
+        assert_eq!(
+            Err(BadDirective("ROBOT 1,0,NORTH".to_string())),
+            parse_level("ROBOT 1,0,NORTH\nMAP\n^0G\n")
+        )
+    }
+
+    #[test]
+    fn complete_on_goal() {
+        let level = corridor_level();
+        let board = level.board.with_robot(Robot::new(Vector::new(2, 0), East));
+
+        assert!(level.is_complete(&board))
+    }
+
+    #[test]
+    fn not_complete_without_required_items() {
+        let level = Level {
+            required_items: vec![Item('a')],
+            ..corridor_level()
+        };
+        let board = level.board.with_robot(Robot::new(Vector::new(2, 0), East));
+
+        assert!(!level.is_complete(&board))
+    }
+
+    #[test]
+    fn score_rewards_unused_budget() {
+        assert_eq!(120, corridor_level().score(2))
+    }
+
+    #[test]
+    fn verify_corridor() {
+        assert_eq!(Ok(2), verify_level(&corridor_level()))
+    }
+
+    #[test]
+    fn verify_over_budget() {
+        let level = Level {
+            command_budget: Some(1),
+            ..corridor_level()
+        };
+
+        assert_eq!(
+            Err(OverBudget {
+                required: 2,
+                budget: 1
+            }),
+            verify_level(&level)
+        )
+    }
+
+    #[test]
+    fn verify_walled_off_goal() {
+        let level = Level {
+            board: Board::empty_with_corner(&Vector::new(2, 0))
+                .with_obstacle_at(Vector::new(1, 0))
+                .with_goal_at(Vector::new(2, 0))
+                .with_robot(Robot::new(Vector::new(0, 0), East)),
+            ..corridor_level()
+        };
+
+        assert_eq!(Err(Unreachable(Vector::new(2, 0))), verify_level(&level))
+    }
+
+    #[test]
+    fn verify_missing_required_item() {
+        let level = Level {
+            required_items: vec![Item('z')],
+            ..corridor_level()
+        };
+
+        assert_eq!(Err(Unsolvable), verify_level(&level))
+    }
+
+    #[test]
+    fn verify_route_past_a_pit() {
+        let level = parse_level("MAP\n@00\n^0G\n").unwrap();
+
+        assert_eq!(Ok(3), verify_level(&level))
+    }
+
+    #[test]
+    fn shortest_solution_tells_apart_items_with_the_same_name() {
+        // Picking up the `a` at the top of the one-way ramp first reaches the same cell carrying
+        // the same items as picking up the `a` at the bottom first, but only the second can go
+        // on to carry both.
+        let level = Level {
+            name: String::new(),
+            board: Board::empty_with_corner(&Vector::at_level(3, 0, 1))
+                .with_ramp_at(Vector::new(2, 0), Ramp::Up)
+                .with_item_at(Vector::new(0, 0), Item('a'))
+                .with_item_at(Vector::at_level(2, 0, 1), Item('a'))
+                .with_goal_at(Vector::at_level(3, 0, 1))
+                .with_robot(Robot::new(Vector::new(1, 0), East)),
+            required_items: vec![Item('a'), Item('a')],
+            command_budget: None,
+        };
+
+        assert_eq!(Some(10), shortest_solution(&level));
+    }
+
+    #[test]
+    fn builtin_levels_are_solvable() {
+        for level in builtin_levels() {
+            assert!(
+                verify_level(&level).is_ok(),
+                "{}: {:?}",
+                level.name,
+                verify_level(&level)
+            );
+        }
+    }
+}
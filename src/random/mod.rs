@@ -0,0 +1,49 @@
+/// A small, fast pseudo-random number generator (SplitMix64), so that anything random can be
+/// replayed exactly from its seed.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SeededRandom {
+    state: u64,
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> SeededRandom {
+        SeededRandom { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 up to but not including `bound`, which must not be 0.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::random::SeededRandom;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut first = SeededRandom::new(42);
+        let mut second = SeededRandom::new(42);
+
+        for _ in 0..10 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+    }
+
+    #[test]
+    fn below_stays_below_bound() {
+        let mut random = SeededRandom::new(7);
+
+        assert!((0..100).all(|_| random.below(3) < 3))
+    }
+}
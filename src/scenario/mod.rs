@@ -0,0 +1,270 @@
+use std::fmt::{Error, Formatter};
+
+use crate::commands::Command;
+use crate::game_execution::output_from_command;
+use crate::game_model::Board;
+use crate::scenario::ScenarioError::{NothingToCheck, UnknownAssertion};
+use crate::session::{run_line, Entry};
+
+/// An assertion in a scenario that did not hold.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Failure {
+    /// Counting lines of the scenario from 1.
+    pub line: usize,
+    pub assertion: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// The assertion, then the expected and actual text line by line, with `-` marking expected
+/// lines that differ and `+` the actual lines in their place.
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "line {}: {}", self.line, self.assertion)?;
+
+        let expected: Vec<&str> = self.expected.lines().collect();
+        let actual: Vec<&str> = self.actual.lines().collect();
+
+        for index in 0..expected.len().max(actual.len()) {
+            match (expected.get(index), actual.get(index)) {
+                (Some(expected_line), Some(actual_line)) if expected_line == actual_line => {
+                    write!(f, "\n    {}", expected_line)?
+                }
+                (expected_line, actual_line) => {
+                    if let Some(expected_line) = expected_line {
+                        write!(f, "\n  - {}", expected_line)?;
+                    }
+                    if let Some(actual_line) = actual_line {
+                        write!(f, "\n  + {}", actual_line)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ScenarioError {
+    UnknownAssertion(usize, String),
+    /// An assertion about a command's output before any command.
+    NothingToCheck(usize),
+}
+
+impl std::fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            UnknownAssertion(line, assertion) => {
+                write!(f, "Unknown assertion on line {}: {}", line, assertion)
+            }
+            NothingToCheck(line) => write!(f, "No command to check on line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+/// Runs a scenario from `board`, returning every assertion that failed.
+///
+/// A scenario is commands, one per line, interleaved with assertions about the command before:
+///
+/// - `EXPECT <output>` that it printed the output;
+/// - `EXPECT_REJECTED` that it was rejected;
+///
+/// or about the board as it stands:
+///
+/// - `EXPECT_MAP`, with the rows of the MAP on the indented lines after it.
+///
+/// Any assertion's expected text can carry on over indented lines. Blank lines and lines starting
+/// with `#` are skipped.
+pub fn run_scenario(board: &Board, text: &str) -> Result<Vec<Failure>, ScenarioError> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut board = board.clone();
+    let mut last_entry: Option<Entry> = None;
+    let mut failures: Vec<Failure> = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index].trim();
+        let number = index + 1;
+        index += 1;
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        if !keyword.starts_with("EXPECT") {
+            let (next_board, entry, _) = run_line(&board, line);
+            board = next_board;
+            last_entry = Some(entry);
+            continue;
+        }
+
+        let mut expected_lines: Vec<&str> = vec![rest.trim()];
+
+        while let Some(continuation) = lines
+            .get(index)
+            .filter(|next| next.starts_with(char::is_whitespace) && !next.trim().is_empty())
+        {
+            expected_lines.push(continuation.trim());
+            index += 1;
+        }
+
+        expected_lines.retain(|expected_line| !expected_line.is_empty());
+        let expected = expected_lines.join("\n");
+
+        let after_last_command = || {
+            last_entry
+                .as_ref()
+                .map(|entry| (format!("{} after {}", keyword, entry.input), entry))
+                .ok_or(NothingToCheck(number))
+        };
+
+        let (assertion, expected, actual) = match keyword {
+            "EXPECT" => {
+                let (assertion, entry) = after_last_command()?;
+                (
+                    assertion,
+                    expected,
+                    entry.output.clone().unwrap_or_default(),
+                )
+            }
+            "EXPECT_REJECTED" => {
+                let (assertion, entry) = after_last_command()?;
+                let is_rejected = entry.command.is_err() || entry.error.is_some();
+                let actual = if is_rejected { "rejected" } else { "accepted" };
+                (assertion, "rejected".to_string(), actual.to_string())
+            }
+            "EXPECT_MAP" => (
+                keyword.to_string(),
+                expected,
+                output_from_command(&board, &Command::Map).unwrap_or_default(),
+            ),
+            _ => return Err(UnknownAssertion(number, line.to_string())),
+        };
+
+        if actual != expected {
+            failures.push(Failure {
+                line: number,
+                assertion,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game_model::Board;
+    use crate::geo::Vector;
+    use crate::scenario::ScenarioError::{NothingToCheck, UnknownAssertion};
+    use crate::scenario::{run_scenario, Failure};
+
+    fn board() -> Board {
+        Board::empty_with_corner(&Vector::new(4, 4))
+    }
+
+    #[test]
+    fn the_shipped_scenarios_pass() {
+        let scenarios = [
+            include_str!("../../scenarios/problem-example-a.robot"),
+            include_str!("../../scenarios/problem-example-b.robot"),
+            include_str!("../../scenarios/problem-example-c.robot"),
+            include_str!("../../scenarios/problem-constraints.robot"),
+        ];
+
+        for scenario in scenarios.iter() {
+            assert_eq!(Ok(Vec::new()), run_scenario(&board(), scenario));
+        }
+    }
+
+    #[test]
+    fn scenario_wrong_expectation() {
+        assert_eq!(
+            Ok(vec![Failure {
+                line: 4,
+                assertion: "EXPECT after REPORT".to_string(),
+                expected: "0,2,NORTH".to_string(),
+                actual: "0,1,NORTH".to_string(),
+            }]),
+            run_scenario(&board(), "PLACE 0,0,NORTH\nMOVE\nREPORT\nEXPECT 0,2,NORTH")
+        );
+    }
+
+    #[test]
+    fn scenario_accepted_command_expected_rejected() {
+        assert_eq!(
+            Ok(vec![Failure {
+                line: 3,
+                assertion: "EXPECT_REJECTED after MOVE".to_string(),
+                expected: "rejected".to_string(),
+                actual: "accepted".to_string(),
+            }]),
+            run_scenario(&board(), "PLACE 0,0,NORTH\nMOVE\nEXPECT_REJECTED")
+        );
+    }
+
+    #[test]
+    fn scenario_unparsed_command_rejected() {
+        assert_eq!(
+            Ok(Vec::new()),
+            run_scenario(&board(), "JUMP\nEXPECT_REJECTED")
+        );
+    }
+
+    #[test]
+    fn parse_scenario_indented_map() {
+        let scenario = "PLACE 1,0,EAST\n\
+                        EXPECT_MAP\n  00000\n  00000\n  00000\n  00000\n  0>000\n\
+                        MOVE\n\
+                        EXPECT_MAP\n  00000\n  00000\n  00000\n  00000\n  0>000";
+
+        let failures = run_scenario(&board(), scenario).unwrap();
+
+        assert_eq!(
+            vec![9],
+            failures
+                .iter()
+                .map(|failure| failure.line)
+                .collect::<Vec<usize>>()
+        );
+        assert_eq!("00000\n00000\n00000\n00000\n00>00", failures[0].actual);
+    }
+
+    #[test]
+    fn scenario_failure_diff() {
+        let failure = Failure {
+            line: 2,
+            assertion: "EXPECT_MAP".to_string(),
+            expected: "000\n^00".to_string(),
+            actual: "000\n>00".to_string(),
+        };
+
+        assert_eq!(
+            "line 2: EXPECT_MAP\n    000\n  - ^00\n  + >00",
+            failure.to_string()
+        );
+    }
+
+    #[test]
+    fn parse_scenario_expectation_without_command() {
+        assert_eq!(
+            Err(NothingToCheck(2)),
+            run_scenario(&board(), "# nothing yet\nEXPECT 0,0,NORTH")
+        );
+    }
+
+    #[test]
+    fn parse_scenario_unknown_assertion() {
+        assert_eq!(
+            Err(UnknownAssertion(1, "EXPECT_BATTERY 3".to_string())),
+            run_scenario(&board(), "EXPECT_BATTERY 3")
+        );
+    }
+}
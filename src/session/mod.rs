@@ -0,0 +1,462 @@
+use std::fmt::{Error, Formatter};
+
+use crate::error::RobotError;
+use crate::game_execution::annotated_map_for;
+use crate::game_execution::simulator::Simulator;
+use crate::game_model::Board;
+use crate::session::SessionError::{BadLine, MissingArgs};
+
+/// Everything that happened for one line of input, as written to a recording.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Entry {
+    pub input: String,
+    /// The parsed command, or why the line could not be parsed.
+    pub command: Result<String, String>,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    /// Whether the board the command produced was valid, and so was kept.
+    pub valid: bool,
+    pub state: String,
+}
+
+/// A recording is a line of the program's arguments, as `args_line` writes it, then for each line
+/// of input:
+///
+/// ```text
+/// > PLACE 0,0,NORTH
+/// COMMAND Place { location: Vector { x: 0, y: 0, z: 0 }, facing: North }
+/// VALID true
+/// STATE ...
+/// ```
+///
+/// with `PARSE ERROR` in place of `COMMAND` for lines that could not be parsed, and `OUTPUT` and
+/// `ERROR` lines for anything printed.
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        writeln!(f, "> {}", self.input)?;
+
+        match &self.command {
+            Ok(command) => writeln!(f, "COMMAND {}", command)?,
+            Err(parsing_error) => writeln!(f, "PARSE ERROR {}", parsing_error)?,
+        }
+
+        for line in self.output.iter().flat_map(|output| output.lines()) {
+            writeln!(f, "OUTPUT {}", line)?;
+        }
+
+        if let Some(error) = &self.error {
+            writeln!(f, "ERROR {}", error)?;
+        }
+
+        writeln!(f, "VALID {}", self.valid)?;
+        writeln!(f, "STATE {}", self.state)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum SessionError {
+    MissingArgs,
+    BadLine(usize, String),
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            MissingArgs => write!(f, "Recording does not start with an ARGS line"),
+            BadLine(number, line) => write!(f, "Bad recording line {}: {}", number, line),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+/// Where a replay first did something different from the recording.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Divergence {
+    /// Counting input lines from 1.
+    pub line: usize,
+    pub input: String,
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "Diverged at input {} ({}): {} was\n  {}\nbut is now\n  {}",
+            self.line, self.input, self.field, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+/// Runs one line of input against the board, returning the board afterwards, what happened as a
+/// recording writes it, and the output or exactly what went wrong. A command that fails, or leaves
+/// the board invalid, leaves the board as it was apart from its history.
+pub fn run_line(board: &Board, input: &str) -> (Board, Entry, Result<Option<String>, RobotError>) {
+    let mut simulator = Simulator::new(board);
+    let (entry, outcome) = simulator.run_line(input);
+
+    (simulator.into_board(), entry, outcome)
+}
+
+/// Runs one line of input as `run_line` does, but without recording it. A failure leaves the
+/// caller's board as it was.
+pub fn try_line(board: &Board, input: &str) -> Result<(Board, Option<String>), RobotError> {
+    let (next_board, _, outcome) = run_line(board, input);
+
+    outcome.map(|output| (next_board, output))
+}
+
+/// The board on one line, the same every run: the robot, the tick, and every level drawn as
+/// MAP draws it with `/` between rows and `|` between levels.
+pub fn board_state(board: &Board) -> String {
+    let levels: Vec<String> = (board.bounds.bottom_left.z..=board.bounds.top_right.z)
+        .map(|level| annotated_map_for(board, level, |_| None).replace('\n', "/"))
+        .collect();
+
+    format!(
+        "{:?} TICK {} MAP {}",
+        board.robot,
+        board.tick,
+        levels.join("|")
+    )
+}
+
+/// The first line of a recording: the program's arguments, each in double quotes with any `"` or
+/// `\` in it escaped by a `\`, so arguments with spaces in them read back whole.
+pub fn args_line(args: &[String]) -> String {
+    let quoted: Vec<String> = args
+        .iter()
+        .map(|arg| format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+
+    format!("ARGS {}", quoted.join(" ")).trim_end().to_string()
+}
+
+/// Reads a recording back into the arguments it was made with and its entries.
+pub fn parse_recording(text: &str) -> Result<(Vec<String>, Vec<Entry>), SessionError> {
+    let mut lines = text.lines().enumerate();
+
+    let args: Vec<String> = match lines.next() {
+        Some((_, line)) if line.starts_with("ARGS") => parse_args(line.trim_start_matches("ARGS"))
+            .ok_or_else(|| BadLine(1, line.to_string()))?,
+        _ => return Err(MissingArgs),
+    };
+
+    let mut entries: Vec<Entry> = Vec::new();
+
+    for (index, line) in lines {
+        let bad_line = || BadLine(index + 1, line.to_string());
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+
+        if key == ">" {
+            entries.push(Entry {
+                input: value.to_string(),
+                command: Ok(String::new()),
+                output: None,
+                error: None,
+                valid: true,
+                state: String::new(),
+            });
+            continue;
+        }
+
+        let entry = entries.last_mut().ok_or_else(bad_line)?;
+
+        match key {
+            "COMMAND" => entry.command = Ok(value.to_string()),
+            "PARSE" => {
+                entry.command = Err(value
+                    .strip_prefix("ERROR ")
+                    .ok_or_else(bad_line)?
+                    .to_string())
+            }
+            "OUTPUT" => {
+                entry.output = Some(match &entry.output {
+                    Some(output) => format!("{}\n{}", output, value),
+                    None => value.to_string(),
+                })
+            }
+            "ERROR" => entry.error = Some(value.to_string()),
+            "VALID" => entry.valid = value.parse().map_err(|_| bad_line())?,
+            "STATE" => entry.state = value.to_string(),
+            "" => {}
+            _ => return Err(bad_line()),
+        }
+    }
+
+    Ok((args, entries))
+}
+
+/// Splits the arguments of an ARGS line, each quoted as `args_line` writes it or a bare word, or
+/// `None` if a quote is never closed.
+fn parse_args(text: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut chars = text.trim_start().chars().peekable();
+
+    while let Some(first) = chars.next() {
+        let mut arg = String::new();
+
+        if first == '"' {
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => arg.push(chars.next()?),
+                    next => arg.push(next),
+                }
+            }
+        } else {
+            arg.push(first);
+
+            while let Some(next) = chars.next_if(|next| !next.is_whitespace()) {
+                arg.push(next);
+            }
+        }
+
+        args.push(arg);
+
+        while chars.next_if(|next| next.is_whitespace()).is_some() {}
+    }
+
+    Some(args)
+}
+
+/// Runs every recorded input again from `board`, returning how many were replayed, or the first
+/// entry where the command, output, error, validity or board state differs.
+pub fn replay(board: &Board, entries: &[Entry]) -> Result<usize, Divergence> {
+    let mut board = board.clone();
+
+    for (index, expected) in entries.iter().enumerate() {
+        let (next_board, actual, _) = run_line(&board, &expected.input);
+
+        let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "nothing".to_string());
+        let show_command = |command: &Result<String, String>| match command {
+            Ok(command) => command.clone(),
+            Err(parsing_error) => format!("parse error: {}", parsing_error),
+        };
+
+        let differences = [
+            (
+                "command",
+                show_command(&expected.command),
+                show_command(&actual.command),
+            ),
+            ("output", show(&expected.output), show(&actual.output)),
+            ("error", show(&expected.error), show(&actual.error)),
+            (
+                "validity",
+                expected.valid.to_string(),
+                actual.valid.to_string(),
+            ),
+            ("board state", expected.state.clone(), actual.state.clone()),
+        ];
+
+        if let Some((field, expected_value, actual_value)) = differences
+            .iter()
+            .find(|(_, expected_value, actual_value)| expected_value != actual_value)
+        {
+            return Err(Divergence {
+                line: index + 1,
+                input: expected.input.clone(),
+                field,
+                expected: expected_value.clone(),
+                actual: actual_value.clone(),
+            });
+        }
+
+        board = next_board;
+    }
+
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::parsing::ParsingError::UnrecognisedCommand;
+    use crate::error::RobotError::{Execution, Parse};
+    use crate::error::Span;
+    use crate::game_execution::ExecutionError::OutOfBounds;
+    use crate::game_model::Board;
+    use crate::geo::Vector;
+    use crate::session::SessionError::{BadLine, MissingArgs};
+    use crate::session::{
+        args_line, parse_recording, replay, run_line, try_line, Divergence, Entry,
+    };
+
+    fn board() -> Board {
+        Board::empty_with_corner(&Vector::new(4, 4))
+    }
+
+    fn record(inputs: &[&str]) -> Vec<Entry> {
+        let mut board = board();
+
+        inputs
+            .iter()
+            .map(|input| {
+                let (next_board, entry, _) = run_line(&board, input);
+                board = next_board;
+                entry
+            })
+            .collect()
+    }
+
+    #[test]
+    fn run_line_with_output() {
+        let entries = record(&["PLACE 0,0,NORTH", "REPORT"]);
+
+        assert_eq!(Ok("Report".to_string()), entries[1].command);
+        assert_eq!(Some("0,0,NORTH".to_string()), entries[1].output);
+        assert!(entries[1].valid);
+    }
+
+    #[test]
+    fn run_line_rejected() {
+        let entries = record(&["PLACE 0,4,NORTH", "MOVE"]);
+
+        assert!(!entries[1].valid);
+        assert_eq!(
+            Some("Location is out of bounds: 0,5".to_string()),
+            entries[1].error
+        );
+        assert_eq!(entries[0].state, entries[1].state);
+    }
+
+    #[test]
+    fn try_line_with_output() {
+        let (board, _) = try_line(&board(), "PLACE 0,0,NORTH").unwrap();
+
+        assert_eq!(
+            Some("0,0,NORTH".to_string()),
+            try_line(&board, "REPORT").unwrap().1
+        );
+    }
+
+    #[test]
+    fn try_line_rejected() {
+        let (board, _) = try_line(&board(), "PLACE 0,4,NORTH").unwrap();
+
+        assert!(matches!(
+            try_line(&board, "MOVE"),
+            Err(Execution(OutOfBounds(location))) if location == Vector::new(0, 5)
+        ));
+    }
+
+    #[test]
+    fn try_line_unparsed() {
+        assert!(matches!(
+            try_line(&board(), "JUMP"),
+            Err(Parse {
+                span: Span { start: 0, end: 4 },
+                error: UnrecognisedCommand(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn run_line_unparsed() {
+        let entries = record(&["JUMP"]);
+
+        assert_eq!(
+            Err("Unrecognised command: JUMP".to_string()),
+            entries[0].command
+        );
+    }
+
+    #[test]
+    fn recording_round_trip() {
+        let entries = record(&["PLACE 0,0,NORTH", "JUMP", "MOVE", "MAP"]);
+        let text: String = std::iter::once("ARGS --hex\n".to_string())
+            .chain(entries.iter().map(Entry::to_string))
+            .collect();
+
+        assert_eq!(
+            Ok((vec!["--hex".to_string()], entries)),
+            parse_recording(&text)
+        );
+    }
+
+    #[test]
+    fn parse_recording_args_with_spaces() {
+        let args = vec![
+            "--level".to_string(),
+            "my levels/first \"easy\" one.level".to_string(),
+            "a\\b".to_string(),
+        ];
+
+        assert_eq!(
+            Ok((args.clone(), vec![])),
+            parse_recording(&format!("{}\n", args_line(&args)))
+        );
+    }
+
+    #[test]
+    fn parse_recording_unquoted_args() {
+        assert_eq!(
+            Ok((vec!["--hex".to_string(), "--seed".to_string()], vec![])),
+            parse_recording("ARGS --hex  --seed\n")
+        );
+    }
+
+    #[test]
+    fn parse_recording_unclosed_quote() {
+        assert_eq!(
+            Err(BadLine(1, "ARGS \"--hex".to_string())),
+            parse_recording("ARGS \"--hex\n")
+        );
+    }
+
+    #[test]
+    fn parse_recording_without_args() {
+        assert_eq!(Err(MissingArgs), parse_recording("> MOVE\n"));
+    }
+
+    #[test]
+    fn parse_recording_unknown_line() {
+        assert_eq!(
+            Err(BadLine(3, "SPEED 9".to_string())),
+            parse_recording("ARGS\n> MOVE\nSPEED 9\n")
+        );
+    }
+
+    #[test]
+    fn replay_matching_recording() {
+        let entries = record(&["PLACE 0,0,NORTH", "MOVE", "RIGHT", "MOVE", "REPORT"]);
+
+        assert_eq!(Ok(5), replay(&board(), &entries));
+    }
+
+    #[test]
+    fn replay_first_divergence() {
+        let mut entries = record(&["PLACE 0,0,NORTH", "MOVE", "REPORT"]);
+        entries[2].output = Some("0,2,NORTH".to_string());
+
+        assert_eq!(
+            Err(Divergence {
+                line: 3,
+                input: "REPORT".to_string(),
+                field: "output",
+                expected: "0,2,NORTH".to_string(),
+                actual: "0,1,NORTH".to_string(),
+            }),
+            replay(&board(), &entries)
+        );
+    }
+
+    #[test]
+    fn replay_divergent_board() {
+        let entries = record(&["PLACE 0,0,NORTH", "MOVE"]);
+        let cluttered = board().with_obstacle_at(Vector::new(3, 3));
+
+        assert_eq!(
+            Some("board state"),
+            replay(&cluttered, &entries)
+                .err()
+                .map(|divergence| divergence.field)
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use crate::geo::grid::{GridShape, SquareGrid};
+use crate::geo::Compass::{self, EightWay, FourWay};
+use crate::geo::Direction::{self, *};
+use crate::random::SeededRandom;
+
+/// Every direction, the four-way ones first, for picking one at random.
+pub(crate) const DIRECTIONS: [Direction; 8] = [
+    North, South, East, West, NorthEast, NorthWest, SouthEast, SouthWest,
+];
+
+pub(crate) fn any_direction(random: &mut SeededRandom) -> Direction {
+    DIRECTIONS[random.below(DIRECTIONS.len())]
+}
+
+pub(crate) fn any_compass(random: &mut SeededRandom) -> Compass {
+    [FourWay, EightWay][random.below(2)]
+}
+
+/// How many turns the same way bring a robot on `grid` back to where it started facing.
+pub(crate) fn turns_in_full_circle(grid: &GridShape) -> usize {
+    match grid {
+        GridShape::Square(SquareGrid { compass: FourWay }) => 4,
+        GridShape::Square(SquareGrid { compass: EightWay }) => 8,
+        GridShape::Hex(_) => 6,
+    }
+}